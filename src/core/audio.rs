@@ -3,7 +3,7 @@ use std::collections::{HashMap, VecDeque};
 use bevy::prelude::*;
 use bevy_kira_audio::{prelude::*, AudioSource};
 
-use crate::core::GameState;
+use crate::core::{enemies::director::Director, GameState};
 
 #[derive(Resource)]
 pub struct MusicChannel;
@@ -99,12 +99,22 @@ fn start_game_music(
     let instance_handle = music_channel.play(asset_handle).loop_from(25.5).handle();
 }
 
+// Fraction of the music volume kept while the director reports no intensity
+const CALM_MUSIC_VOLUME: f64 = 0.7;
+
 fn update_volume(
     music_channel: Res<AudioChannel<MusicChannel>>,
     music_volume: Res<MusicVolume>,
     sfx_channel: Res<AudioChannel<SFXChannel>>,
     sfx_volume: Res<SFXVolume>,
+    game_state: Res<State<GameState>>,
+    director: Res<Director>,
 ) {
-    music_channel.set_volume(music_volume.0);
+    let intensity_volume = if *game_state.get() == GameState::Game {
+        CALM_MUSIC_VOLUME + (1. - CALM_MUSIC_VOLUME) * director.intensity() as f64
+    } else {
+        1.
+    };
+    music_channel.set_volume(music_volume.0 * intensity_volume);
     sfx_channel.set_volume(sfx_volume.0);
 }
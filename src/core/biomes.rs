@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use super::{
+    environment::{chunk_at, WorldSeed},
+    gui::ShowBanner,
+    items::Item,
+    player::Player,
+    GameState, PauseState,
+};
+
+pub struct BiomesPlugin;
+
+impl Plugin for BiomesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CurrentBiome(Biome::Aegean))
+            .add_systems(
+                Update,
+                update_current_biome
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(GameState::Game), reset_current_biome);
+    }
+}
+
+// Chunks across one noise cell, roughly how wide a biome region is
+const BIOME_SCALE: f32 = 12.;
+// Chunks around the start that are always calm
+const START_RADIUS: i32 = 4;
+// Keeps the two noise fields from lining up
+const DEPTH_SALT: u64 = 0x5DEE_CE66_D1CE_4E5B;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Biome {
+    Aegean,
+    StormSea,
+    CoralReef,
+    IceFloes,
+    Styx,
+}
+
+pub struct BiomeDef {
+    pub name: &'static str,
+    pub water_color: Color,
+    pub layer_color: Color,
+    pub layer_speed: f32,
+    // Drawn over the main layer, scrolling the other way
+    pub overlay: Option<Color>,
+    pub obstacle_color: Color,
+    // Expected obstacles per chunk
+    pub obstacle_rate: f32,
+    // Replaces the weight of these items where the spawn table has them
+    pub item_weights: &'static [(Item, i32)],
+    // Chance of each hazard per chunk
+    pub whirlpool_rate: f32,
+    pub reef_rate: f32,
+}
+
+impl Biome {
+    pub fn def(&self) -> BiomeDef {
+        match self {
+            Biome::Aegean => BiomeDef {
+                name: "The Aegean",
+                water_color: Color::WHITE,
+                layer_color: Color::rgba(1., 1., 1., 0.5),
+                layer_speed: 3.,
+                overlay: None,
+                obstacle_color: Color::WHITE,
+                obstacle_rate: 0.8,
+                item_weights: &[],
+                whirlpool_rate: 0.03,
+                reef_rate: 0.05,
+            },
+            Biome::StormSea => BiomeDef {
+                name: "Storm Sea",
+                water_color: Color::rgb(0.55, 0.6, 0.7),
+                layer_color: Color::rgba(0.8, 0.85, 0.9, 0.6),
+                layer_speed: 9.,
+                overlay: Some(Color::rgba(0.9, 0.9, 1., 0.3)),
+                obstacle_color: Color::rgb(0.6, 0.6, 0.65),
+                obstacle_rate: 0.5,
+                item_weights: &[(Item::ZeusThunderbolt, 4)],
+                whirlpool_rate: 0.12,
+                reef_rate: 0.02,
+            },
+            Biome::CoralReef => BiomeDef {
+                name: "Coral Reef",
+                water_color: Color::rgb(0.7, 1., 0.95),
+                layer_color: Color::rgba(1., 0.8, 0.7, 0.5),
+                layer_speed: 2.,
+                overlay: None,
+                obstacle_color: Color::rgb(1., 0.6, 0.55),
+                obstacle_rate: 2.,
+                item_weights: &[(Item::Spear, 8), (Item::PoseidonTrident, 3)],
+                whirlpool_rate: 0.02,
+                reef_rate: 0.4,
+            },
+            Biome::IceFloes => BiomeDef {
+                name: "Ice Floes",
+                water_color: Color::rgb(0.8, 0.9, 1.),
+                layer_color: Color::rgba(1., 1., 1., 0.7),
+                layer_speed: 1.,
+                overlay: None,
+                obstacle_color: Color::rgb(0.85, 0.95, 1.),
+                obstacle_rate: 1.5,
+                item_weights: &[(Item::Bow, 8)],
+                whirlpool_rate: 0.03,
+                reef_rate: 0.05,
+            },
+            Biome::Styx => BiomeDef {
+                name: "The Styx",
+                water_color: Color::rgb(0.35, 0.25, 0.45),
+                layer_color: Color::rgba(0.6, 0.4, 0.8, 0.5),
+                layer_speed: 1.5,
+                overlay: Some(Color::rgba(0.4, 0.9, 0.6, 0.2)),
+                obstacle_color: Color::rgb(0.4, 0.35, 0.45),
+                obstacle_rate: 0.4,
+                item_weights: &[(Item::GreekFire, 6), (Item::PoseidonTrident, 2)],
+                whirlpool_rate: 0.08,
+                reef_rate: 0.,
+            },
+        }
+    }
+}
+
+// The biome the player is sailing through
+#[derive(Resource)]
+pub struct CurrentBiome(pub Biome);
+
+// Mixes a seed with a grid cell, for noise lattices and per chunk rolls alike
+pub fn hash_cell(seed: u64, cell: IVec2) -> u64 {
+    let mut h = seed
+        ^ (cell.x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (cell.y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^ (h >> 31)
+}
+
+fn hash(seed: u64, x: i32, y: i32) -> f32 {
+    (hash_cell(seed, IVec2::new(x, y)) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// Smoothed value noise in 0..1
+pub fn value_noise(seed: u64, position: Vec2) -> f32 {
+    let cell = position.floor();
+    let t = position - cell;
+    let t = t * t * (3. - 2. * t);
+    let (x, y) = (cell.x as i32, cell.y as i32);
+
+    let bottom = hash(seed, x, y) * (1. - t.x) + hash(seed, x + 1, y) * t.x;
+    let top = hash(seed, x, y + 1) * (1. - t.x) + hash(seed, x + 1, y + 1) * t.x;
+    bottom * (1. - t.y) + top * t.y
+}
+
+pub fn biome_at(world_seed: &WorldSeed, chunk: IVec2) -> Biome {
+    if chunk.x.abs() <= START_RADIUS && chunk.y.abs() <= START_RADIUS {
+        return Biome::Aegean;
+    }
+
+    let position = chunk.as_vec2() / BIOME_SCALE;
+    let heat = value_noise(world_seed.0, position);
+    let depth = value_noise(world_seed.0 ^ DEPTH_SALT, position);
+
+    if depth > 0.7 {
+        Biome::Styx
+    } else if heat < 0.3 {
+        Biome::IceFloes
+    } else if heat > 0.7 {
+        Biome::CoralReef
+    } else if depth > 0.55 {
+        Biome::StormSea
+    } else {
+        Biome::Aegean
+    }
+}
+
+pub fn biome_at_position(world_seed: &WorldSeed, position: Vec2) -> Biome {
+    biome_at(world_seed, chunk_at(position))
+}
+
+fn update_current_biome(
+    world_seed: Res<WorldSeed>,
+    mut current_biome: ResMut<CurrentBiome>,
+    player_query: Query<&Transform, With<Player>>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let biome = biome_at_position(&world_seed, player_transform.translation.xy());
+    if biome != current_biome.0 {
+        current_biome.0 = biome;
+        ev_show_banner.send(ShowBanner(biome.def().name.into()));
+    }
+}
+
+// Every run starts in the calm
+fn reset_current_biome(mut current_biome: ResMut<CurrentBiome>) {
+    current_biome.0 = Biome::Aegean;
+}
@@ -0,0 +1,560 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_tweening::{lens::SpriteColorLens, Animator, EaseMethod, Tween};
+use rand::{thread_rng, Rng};
+use std::{collections::HashMap, f32::consts::PI, time::Duration};
+
+use crate::core::{GameState, PauseState};
+
+use super::{
+    enemies::{DamageEvent, DamageKind, Enemy, Targetable},
+    gui::ShowBanner,
+    obstacles::sensor_groups,
+    player::{LevelUp, Player},
+    DistanceDespawn, GameDespawn, IngameTime, Movement, TweenDespawn, YSort,
+};
+
+pub struct CompanionsPlugin;
+
+impl Plugin for CompanionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CompanionOrders(None))
+            .add_event::<RecruitCompanion>()
+            .add_systems(
+                OnEnter(GameState::Game),
+                (setup_companion_hud, reset_companion_orders),
+            )
+            .add_systems(OnExit(GameState::Game), cleanup_companion_hud)
+            .add_systems(
+                Update,
+                (
+                    spawn_companion_pickups,
+                    handle_companion_pickups,
+                    recruit_on_level_up,
+                    recruit_companions,
+                    cycle_companion_orders,
+                    update_companions,
+                    handle_companion_collisions,
+                    expire_companions,
+                    update_companion_hud,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+const MAX_COMPANIONS: usize = 3;
+// Per second
+const PICKUP_SPAWN_CHANCE: f32 = 0.005;
+const PICKUP_DISTANCE: f32 = 400.;
+// Every this many levels a companion joins
+const COMPANION_LEVELS: u32 = 15;
+
+// Companions drop whatever they are doing past this and swim back
+const LEASH_DISTANCE: f32 = 600.;
+const FOLLOW_DISTANCE: f32 = 64.;
+const FOLLOW_RANGE: f32 = 150.;
+const GUARD_RADIUS: f32 = 96.;
+const GUARD_RANGE: f32 = 250.;
+// Radians per second
+const GUARD_ORBIT_SPEED: f32 = 1.5;
+const HUNT_RANGE: f32 = 500.;
+// Seconds before the same enemy can be bitten again
+const HIT_COOLDOWN: f32 = 1.;
+const FADE_DURATION: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Companion {
+    Blahaj,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CompanionAI {
+    // Trails the player and only bites what gets close
+    Follow,
+    // Circles the player and intercepts anything nearby
+    Guard,
+    // Roams after the nearest enemy
+    Hunt,
+}
+
+struct CompanionDef {
+    name: &'static str,
+    texture: &'static str,
+    // Capsule half length and radius
+    collider: (f32, f32),
+    ai: CompanionAI,
+    damage: f32,
+    kind: DamageKind,
+    acceleration: f32,
+    max_speed: f32,
+    // Seconds until it swims off, None stays for the rest of the run
+    lifetime: Option<f32>,
+}
+
+impl Companion {
+    fn def(&self) -> CompanionDef {
+        match self {
+            Companion::Blahaj => CompanionDef {
+                name: "Blahaj",
+                texture: "sprites/other/blahaj.png",
+                collider: (16., 8.),
+                ai: CompanionAI::Hunt,
+                damage: 20.,
+                kind: DamageKind::Physical,
+                acceleration: 500.,
+                max_speed: 200.,
+                lifetime: Some(90.),
+            },
+        }
+    }
+}
+
+// Sent to add a companion to the crew, from pickups, level ups or anything else
+#[derive(Event)]
+pub struct RecruitCompanion(pub Companion);
+
+// Overrides every companion's own AI when set
+#[derive(Resource)]
+struct CompanionOrders(Option<CompanionAI>);
+
+#[derive(Component)]
+pub struct CompanionUnit {
+    companion: Companion,
+    ai: CompanionAI,
+    lifetime: Option<f32>,
+    recruited_at: f32,
+    // When each enemy was last bitten
+    last_hits: HashMap<Entity, f32>,
+    // Where around the player it is while guarding
+    orbit: f32,
+}
+
+#[derive(Component)]
+struct CompanionPickup(Companion);
+
+fn reset_companion_orders(mut companion_orders: ResMut<CompanionOrders>) {
+    companion_orders.0 = None;
+}
+
+fn spawn_companion_pickups(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    pickup_query: Query<(), With<CompanionPickup>>,
+    companion_query: Query<(), With<CompanionUnit>>,
+) {
+    if !pickup_query.is_empty() || companion_query.iter().count() >= MAX_COMPANIONS {
+        return;
+    }
+
+    let mut rng = thread_rng();
+    if !rng.gen_bool((PICKUP_SPAWN_CHANCE * time.delta_seconds()).into()) {
+        return;
+    }
+
+    let player_transform = player_query.get_single().unwrap();
+    let random_angle = rng.gen_range((0.)..(2. * PI));
+    let spawn_position =
+        Vec2::from_angle(random_angle) * PICKUP_DISTANCE + player_transform.translation.xy();
+
+    let companion = Companion::Blahaj;
+    let def = companion.def();
+
+    commands.spawn((
+        CompanionPickup(companion),
+        Collider::ball(24.),
+        Sensor,
+        sensor_groups(),
+        ActiveEvents::COLLISION_EVENTS,
+        SpriteBundle {
+            texture: asset_server.load(def.texture),
+            transform: Transform::from_translation(spawn_position.extend(0.)),
+            ..default()
+        },
+        YSort(0.),
+        DistanceDespawn,
+        GameDespawn,
+    ));
+}
+
+fn handle_companion_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    pickup_query: Query<(&CompanionPickup, Entity)>,
+    player_query: Query<&Player>,
+    mut ev_recruit: EventWriter<RecruitCompanion>,
+) {
+    for collision_event in collision_events.read() {
+        if let CollisionEvent::Started(entity_one, entity_two, _) = collision_event {
+            let entities = [entity_one, entity_two];
+            let mut maybe_pickup = pickup_query.iter_many(entities);
+            let mut maybe_player = player_query.iter_many(entities);
+
+            if let (Some((pickup, pickup_entity)), Some(_player)) =
+                (maybe_pickup.fetch_next(), maybe_player.fetch_next())
+            {
+                commands.entity(pickup_entity).despawn_recursive();
+                ev_recruit.send(RecruitCompanion(pickup.0));
+            }
+        }
+    }
+}
+
+fn recruit_on_level_up(
+    mut ev_level_up: EventReader<LevelUp>,
+    mut ev_recruit: EventWriter<RecruitCompanion>,
+) {
+    for event in ev_level_up.read() {
+        if event.0 % COMPANION_LEVELS == 0 {
+            ev_recruit.send(RecruitCompanion(Companion::Blahaj));
+        }
+    }
+}
+
+fn recruit_companions(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ingame_time: Res<IngameTime>,
+    companion_orders: Res<CompanionOrders>,
+    mut ev_recruit: EventReader<RecruitCompanion>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+    player_query: Query<&Transform, With<Player>>,
+    mut companion_query: Query<&mut CompanionUnit>,
+) {
+    let player_transform = player_query.get_single().unwrap();
+    let mut crew = companion_query.iter().count();
+
+    for event in ev_recruit.read() {
+        // Make room by sending the oldest one off
+        if crew >= MAX_COMPANIONS {
+            if let Some(mut oldest) = companion_query
+                .iter_mut()
+                .filter(|companion| companion.lifetime != Some(0.))
+                .min_by(|a, b| a.recruited_at.total_cmp(&b.recruited_at))
+            {
+                oldest.lifetime = Some(0.);
+            }
+        } else {
+            crew += 1;
+        }
+
+        let def = event.0.def();
+        let mut rng = thread_rng();
+        let spawn_position = player_transform.translation.xy()
+            + Vec2::from_angle(rng.gen_range((0.)..(2. * PI))) * FOLLOW_DISTANCE;
+
+        commands.spawn((
+            CompanionUnit {
+                companion: event.0,
+                ai: companion_orders.0.unwrap_or(def.ai),
+                lifetime: def.lifetime,
+                recruited_at: ingame_time.0,
+                last_hits: HashMap::new(),
+                orbit: rng.gen_range((0.)..(2. * PI)),
+            },
+            Collider::capsule_x(def.collider.0, def.collider.1),
+            Sensor,
+            sensor_groups(),
+            ActiveEvents::COLLISION_EVENTS,
+            ActiveCollisionTypes::STATIC_STATIC,
+            SpriteBundle {
+                texture: asset_server.load(def.texture),
+                transform: Transform::from_translation(spawn_position.extend(0.)),
+                ..default()
+            },
+            Movement {
+                velocity: Vec2::ZERO,
+                friction: 1.,
+                max_speed: def.max_speed,
+            },
+            YSort(0.),
+            GameDespawn,
+        ));
+
+        ev_show_banner.send(ShowBanner(format!("{} joins the crew", def.name)));
+    }
+}
+
+fn cycle_companion_orders(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut companion_orders: ResMut<CompanionOrders>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+    mut companion_query: Query<&mut CompanionUnit>,
+) {
+    if !keys.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+
+    let (orders, label) = match companion_orders.0 {
+        None => (Some(CompanionAI::Follow), "follow"),
+        Some(CompanionAI::Follow) => (Some(CompanionAI::Guard), "guard"),
+        Some(CompanionAI::Guard) => (Some(CompanionAI::Hunt), "hunt"),
+        Some(CompanionAI::Hunt) => (None, "free"),
+    };
+    companion_orders.0 = orders;
+
+    for mut companion in companion_query.iter_mut() {
+        companion.ai = orders.unwrap_or(companion.companion.def().ai);
+    }
+
+    ev_show_banner.send(ShowBanner(format!("Companions: {}", label)));
+}
+
+fn update_companions(
+    time: Res<Time>,
+    ingame_time: Res<IngameTime>,
+    player_query: Query<&Transform, With<Player>>,
+    mut companion_query: Query<
+        (&mut CompanionUnit, &mut Movement, &Transform, &mut Sprite),
+        Without<Player>,
+    >,
+    enemies_query: Query<
+        (Entity, &Transform),
+        (With<Enemy>, With<Targetable>, Without<CompanionUnit>),
+    >,
+) {
+    let player_position = player_query.get_single().unwrap().translation.xy();
+
+    for (mut companion, mut movement, transform, mut sprite) in companion_query.iter_mut() {
+        let position = transform.translation.xy();
+
+        companion
+            .last_hits
+            .retain(|_, hit| ingame_time.0 - *hit < HIT_COOLDOWN);
+
+        let (anchor, range) = match companion.ai {
+            CompanionAI::Follow => (player_position, FOLLOW_RANGE),
+            CompanionAI::Guard => (player_position, GUARD_RANGE),
+            CompanionAI::Hunt => (position, HUNT_RANGE),
+        };
+
+        let target = if position.distance(player_position) > LEASH_DISTANCE {
+            None
+        } else {
+            enemies_query
+                .iter()
+                .filter(|(enemy, _)| !companion.last_hits.contains_key(enemy))
+                .map(|(_, enemy_transform)| enemy_transform.translation.xy())
+                .filter(|enemy_position| enemy_position.distance(anchor) < range)
+                .min_by(|a, b| a.distance(anchor).total_cmp(&b.distance(anchor)))
+        };
+
+        let destination = match (target, companion.ai) {
+            (Some(enemy_position), _) => enemy_position,
+            (None, CompanionAI::Guard) => {
+                companion.orbit += GUARD_ORBIT_SPEED * time.delta_seconds();
+                player_position + Vec2::from_angle(companion.orbit) * GUARD_RADIUS
+            }
+            (None, _) => {
+                player_position + (position - player_position).normalize_or_zero() * FOLLOW_DISTANCE
+            }
+        };
+
+        let offset = destination - position;
+        // Let friction settle it instead of jittering around the spot
+        if target.is_some() || offset.length() > FOLLOW_DISTANCE / 4. {
+            let acceleration = companion.companion.def().acceleration;
+            movement.velocity += offset.normalize_or_zero() * acceleration * time.delta_seconds();
+        }
+
+        if movement.velocity.x < 0. {
+            sprite.flip_x = true;
+        } else if movement.velocity.x > 0. {
+            sprite.flip_x = false;
+        }
+    }
+}
+
+fn handle_companion_collisions(
+    ingame_time: Res<IngameTime>,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut companion_query: Query<&mut CompanionUnit>,
+    enemy_query: Query<Entity, (With<Enemy>, With<Targetable>)>,
+    mut ev_damage: EventWriter<DamageEvent>,
+) {
+    for collision_event in collision_events.read() {
+        if let CollisionEvent::Started(entity_one, entity_two, _) = collision_event {
+            let entities = [entity_one, entity_two];
+            let mut maybe_companion = companion_query.iter_many_mut(entities);
+            let mut maybe_enemy = enemy_query.iter_many(entities);
+
+            if let (Some(mut companion), Some(enemy_entity)) =
+                (maybe_companion.fetch_next(), maybe_enemy.fetch_next())
+            {
+                if companion.last_hits.contains_key(&enemy_entity) {
+                    continue;
+                }
+                companion.last_hits.insert(enemy_entity, ingame_time.0);
+
+                let def = companion.companion.def();
+                ev_damage.send(DamageEvent {
+                    damage: def.damage,
+                    entity: enemy_entity,
+                    kind: def.kind,
+                });
+            }
+        }
+    }
+}
+
+fn expire_companions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut companion_query: Query<(Entity, &mut CompanionUnit)>,
+) {
+    for (entity, mut companion) in companion_query.iter_mut() {
+        let Some(lifetime) = &mut companion.lifetime else {
+            continue;
+        };
+        *lifetime -= time.delta_seconds();
+        if *lifetime > 0. {
+            continue;
+        }
+
+        let fade_tween = Tween::new(
+            EaseMethod::Linear,
+            Duration::from_secs_f32(FADE_DURATION),
+            SpriteColorLens {
+                start: Color::WHITE,
+                end: Color::WHITE.with_a(0.),
+            },
+        )
+        .with_completed_event(0);
+
+        commands
+            .entity(entity)
+            .insert((TweenDespawn, Animator::new(fade_tween)))
+            .remove::<(CompanionUnit, Collider)>();
+    }
+}
+
+#[derive(Component)]
+struct CompanionHud;
+
+#[derive(Component)]
+struct CompanionSlot(usize);
+
+#[derive(Component)]
+struct CompanionIcon(usize);
+
+#[derive(Component)]
+struct CompanionLifetimeBar(usize);
+
+fn setup_companion_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            CompanionHud,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(24.),
+                    bottom: Val::Px(24.),
+                    column_gap: Val::Px(12.),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for index in 0..MAX_COMPANIONS {
+                parent
+                    .spawn((
+                        CompanionSlot(index),
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(4.),
+                                border: UiRect::all(Val::Px(2.)),
+                                ..default()
+                            },
+                            border_color: Color::rgba(1., 1., 1., 0.8).into(),
+                            background_color: Color::rgba(1., 1., 1., 0.25).into(),
+                            visibility: Visibility::Hidden,
+                            ..default()
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            CompanionIcon(index),
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(32.),
+                                    height: Val::Px(32.),
+                                    ..default()
+                                },
+                                background_color: Color::WHITE.into(),
+                                ..default()
+                            },
+                        ));
+                        parent.spawn((
+                            CompanionLifetimeBar(index),
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.),
+                                    height: Val::Px(4.),
+                                    ..default()
+                                },
+                                background_color: Color::rgb(0.3, 0.7, 1.).into(),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn cleanup_companion_hud(mut commands: Commands, hud_query: Query<Entity, With<CompanionHud>>) {
+    for hud in hud_query.iter() {
+        commands.entity(hud).despawn_recursive();
+    }
+}
+
+fn update_companion_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    companion_query: Query<&CompanionUnit>,
+    mut slot_query: Query<(&CompanionSlot, &mut Visibility)>,
+    mut icon_query: Query<(Entity, &CompanionIcon, Option<&mut UiImage>)>,
+    mut bar_query: Query<(&CompanionLifetimeBar, &mut Style)>,
+) {
+    let mut crew: Vec<&CompanionUnit> = companion_query.iter().collect();
+    crew.sort_by(|a, b| a.recruited_at.total_cmp(&b.recruited_at));
+
+    for (slot, mut visibility) in slot_query.iter_mut() {
+        *visibility = if slot.0 < crew.len() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for (icon_entity, icon, icon_image) in icon_query.iter_mut() {
+        let Some(companion) = crew.get(icon.0) else {
+            continue;
+        };
+        let texture: Handle<Image> = asset_server.load(companion.companion.def().texture);
+
+        if let Some(mut icon_image) = icon_image {
+            icon_image.texture = texture;
+        } else {
+            commands.entity(icon_entity).insert(UiImage {
+                texture,
+                ..default()
+            });
+        }
+    }
+
+    for (bar, mut style) in bar_query.iter_mut() {
+        let fill = crew.get(bar.0).map_or(0., |companion| {
+            match (companion.lifetime, companion.companion.def().lifetime) {
+                (Some(lifetime), Some(max_lifetime)) => (lifetime / max_lifetime).max(0.),
+                _ => 1.,
+            }
+        });
+        style.width = Val::Percent(fill * 100.);
+    }
+}
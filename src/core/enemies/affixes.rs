@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+
+use crate::core::{
+    obstacles::sensor_groups, player::Player, GameDespawn, GameState, Movement, PauseState,
+    TimedDespawn, YSort,
+};
+
+use super::{
+    ai::EnemyAI, death::EnemyKilled, ContactEnemy, Enemy, EnemyKnockback, EnemyXp, Health,
+};
+
+pub struct AffixesPlugin;
+
+impl Plugin for AffixesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_affixes,
+                handle_regeneration,
+                handle_vampiric_contact,
+                spawn_detonations,
+                handle_detonations,
+            )
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Affix {
+    Shielded,
+    Swift,
+    Volatile,
+    Vampiric,
+    Splitting,
+    Regenerating,
+    Giant,
+}
+
+impl Affix {
+    fn tint(&self) -> Color {
+        match self {
+            Affix::Shielded => Color::rgb(0.5, 0.7, 1.),
+            Affix::Swift => Color::rgb(1., 1., 0.5),
+            Affix::Volatile => Color::rgb(1., 0.5, 0.3),
+            Affix::Vampiric => Color::rgb(0.8, 0.3, 0.5),
+            Affix::Splitting => Color::rgb(0.8, 0.6, 1.),
+            Affix::Regenerating => Color::rgb(0.5, 1., 0.5),
+            Affix::Giant => Color::rgb(1., 0.8, 0.6),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone)]
+pub struct AffixRate {
+    pub affix: Affix,
+    pub weight: i32,
+}
+
+// Per spawn-table band: chance for a spawn to be elite and which affixes it can roll
+#[derive(PartialEq, Eq, Clone)]
+pub struct EliteRolls {
+    pub percent: i32,
+    pub max_affixes: usize,
+    pub affix_rates: Vec<AffixRate>,
+}
+
+impl EliteRolls {
+    pub fn none() -> Self {
+        EliteRolls {
+            percent: 0,
+            max_affixes: 0,
+            affix_rates: vec![],
+        }
+    }
+
+    pub fn basic(percent: i32) -> Self {
+        EliteRolls {
+            percent,
+            max_affixes: 1,
+            affix_rates: vec![
+                AffixRate {
+                    affix: Affix::Shielded,
+                    weight: 2,
+                },
+                AffixRate {
+                    affix: Affix::Swift,
+                    weight: 2,
+                },
+                AffixRate {
+                    affix: Affix::Regenerating,
+                    weight: 1,
+                },
+            ],
+        }
+    }
+
+    pub fn all(percent: i32, max_affixes: usize) -> Self {
+        EliteRolls {
+            percent,
+            max_affixes,
+            affix_rates: vec![
+                AffixRate {
+                    affix: Affix::Shielded,
+                    weight: 3,
+                },
+                AffixRate {
+                    affix: Affix::Swift,
+                    weight: 3,
+                },
+                AffixRate {
+                    affix: Affix::Regenerating,
+                    weight: 2,
+                },
+                AffixRate {
+                    affix: Affix::Volatile,
+                    weight: 2,
+                },
+                AffixRate {
+                    affix: Affix::Vampiric,
+                    weight: 2,
+                },
+                AffixRate {
+                    affix: Affix::Splitting,
+                    weight: 1,
+                },
+                AffixRate {
+                    affix: Affix::Giant,
+                    weight: 1,
+                },
+            ],
+        }
+    }
+
+    // `chance_multiplier` scales the elite chance, capped at always
+    pub fn roll(&self, rng: &mut impl Rng, chance_multiplier: f32) -> Vec<Affix> {
+        let mut affixes = Vec::new();
+        let chance = (self.percent as f64 / 100. * chance_multiplier as f64).clamp(0., 1.);
+        if self.affix_rates.is_empty() || self.max_affixes == 0 || !rng.gen_bool(chance) {
+            return affixes;
+        }
+
+        let affix_weights = WeightedIndex::new(
+            &self
+                .affix_rates
+                .iter()
+                .map(|affix| affix.weight)
+                .collect::<Vec<i32>>(),
+        )
+        .unwrap();
+
+        let affix_count = rng.gen_range(1..=self.max_affixes.min(self.affix_rates.len()));
+        while affixes.len() < affix_count {
+            let affix = self.affix_rates[affix_weights.sample(rng)].affix;
+            if !affixes.contains(&affix) {
+                affixes.push(affix);
+            }
+        }
+
+        affixes
+    }
+}
+
+#[derive(Component)]
+pub struct Elite {
+    pub affixes: Vec<Affix>,
+}
+
+impl Elite {
+    pub fn has(&self, affix: Affix) -> bool {
+        self.affixes.contains(&affix)
+    }
+
+    pub fn tint(&self) -> Color {
+        let total = self.affixes.iter().fold(Vec3::ZERO, |total, affix| {
+            let tint = affix.tint();
+            total + Vec3::new(tint.r(), tint.g(), tint.b())
+        }) / self.affixes.len().max(1) as f32;
+        Color::rgb(total.x, total.y, total.z)
+    }
+}
+
+#[derive(Component)]
+pub struct Shield {
+    pub hits: i32,
+}
+
+const AFFIX_XP_BONUS: f32 = 0.5;
+const SHIELD_HITS: i32 = 3;
+const SWIFT_MULTIPLIER: f32 = 1.5;
+const GIANT_HEALTH_MULTIPLIER: f32 = 2.5;
+const GIANT_SCALE: f32 = 1.5;
+const REGENERATION_RATE: f32 = 0.03;
+const VAMPIRIC_HEAL: f32 = 0.25;
+const DETONATION_FUSE: f32 = 0.6;
+const DETONATION_RADIUS: f32 = 72.;
+
+fn apply_affixes(
+    mut commands: Commands,
+    mut elite_query: Query<
+        (
+            Entity,
+            &Elite,
+            &mut Health,
+            &mut EnemyXp,
+            &mut Movement,
+            &mut Sprite,
+            &mut Transform,
+            Option<&mut EnemyAI>,
+        ),
+        Added<Elite>,
+    >,
+) {
+    for (elite_entity, elite, mut health, mut xp, mut movement, mut sprite, mut transform, ai) in
+        elite_query.iter_mut()
+    {
+        xp.0 *= 1. + AFFIX_XP_BONUS * elite.affixes.len() as f32;
+        sprite.color = elite.tint();
+
+        if elite.has(Affix::Shielded) {
+            commands
+                .entity(elite_entity)
+                .insert(Shield { hits: SHIELD_HITS });
+        }
+
+        if elite.has(Affix::Swift) {
+            movement.max_speed *= SWIFT_MULTIPLIER;
+            if let Some(mut ai) = ai {
+                ai.speed_multiplier *= SWIFT_MULTIPLIER;
+            }
+        }
+
+        if elite.has(Affix::Giant) {
+            health.max_health *= GIANT_HEALTH_MULTIPLIER;
+            health.health = health.max_health;
+            transform.scale *= GIANT_SCALE;
+        }
+    }
+}
+
+fn handle_regeneration(time: Res<Time>, mut elite_query: Query<(&Elite, &mut Health)>) {
+    for (elite, mut health) in elite_query.iter_mut() {
+        if !elite.has(Affix::Regenerating) || health.health < 0. {
+            continue;
+        }
+
+        health.health = (health.health
+            + health.max_health * REGENERATION_RATE * time.delta_seconds())
+        .min(health.max_health);
+    }
+}
+
+fn handle_vampiric_contact(
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<&Player>,
+    mut elite_query: Query<(&Elite, &mut Health), With<Enemy>>,
+) {
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity_one, entity_two, _) = collision_event else {
+            continue;
+        };
+
+        let entities = [entity_one, entity_two];
+        let mut maybe_player = player_query.iter_many(entities);
+        let mut maybe_elite = elite_query.iter_many_mut(entities);
+
+        if let (Some(_player), Some((elite, mut health))) =
+            (maybe_player.fetch_next(), maybe_elite.fetch_next())
+        {
+            if !elite.has(Affix::Vampiric) {
+                continue;
+            }
+
+            health.health =
+                (health.health + health.max_health * VAMPIRIC_HEAL).min(health.max_health);
+        }
+    }
+}
+
+#[derive(Component)]
+struct Detonation {
+    timer: Timer,
+}
+
+fn spawn_detonations(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    elite_query: Query<&Elite>,
+) {
+    for event in ev_enemy_killed.read() {
+        let Ok(elite) = elite_query.get(event.entity) else {
+            continue;
+        };
+
+        if !elite.has(Affix::Volatile) {
+            continue;
+        }
+
+        commands.spawn((
+            Detonation {
+                timer: Timer::from_seconds(DETONATION_FUSE, TimerMode::Once),
+            },
+            SpriteBundle {
+                texture: asset_server.load("sprites/projectiles/greek_fire.png"),
+                transform: Transform {
+                    translation: event.position.extend(0.),
+                    scale: Vec3::splat(DETONATION_RADIUS / 64.),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: Affix::Volatile.tint().with_a(0.4),
+                    ..default()
+                },
+                ..default()
+            },
+            YSort(-1.),
+            GameDespawn,
+        ));
+    }
+}
+
+fn handle_detonations(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut detonation_query: Query<(Entity, &mut Detonation, &mut Sprite)>,
+) {
+    for (detonation_entity, mut detonation, mut sprite) in detonation_query.iter_mut() {
+        detonation.timer.tick(time.delta());
+
+        if !detonation.timer.just_finished() {
+            continue;
+        }
+
+        sprite.color = Affix::Volatile.tint();
+
+        commands.entity(detonation_entity).insert((
+            ContactEnemy,
+            EnemyKnockback { knockback: 400. },
+            Sensor,
+            sensor_groups(),
+            Collider::ball(64.),
+            TimedDespawn { delay: 0.2 },
+        ));
+    }
+}
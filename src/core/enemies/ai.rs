@@ -1,13 +1,13 @@
 use bevy::{prelude::*, transform};
-use bevy_rapier2d::prelude::*;
+use rand::{thread_rng, Rng};
 use std::f32::consts::PI;
 
-use crate::core::{
-    player::Player, DistanceDespawn, GameDespawn, GameState, Movement, PauseState, TimedDespawn,
-    YSort,
-};
+use crate::core::{player::Player, GameState, Movement, PauseState};
 
-use super::{ContactEnemy, EnemyKnockback};
+use super::{
+    defs::{AIStateDef, BehaviorDef, ConditionDef, EnemyDef},
+    EnemyArchetype, Health,
+};
 
 pub struct AIPlugin;
 
@@ -15,55 +15,78 @@ impl Plugin for AIPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (
-                handle_chase_ai,
-                handle_surround_ai,
-                handle_ranged_ai,
-                handle_kraken_ai,
-                update_linear_projectiles,
-            )
+            (update_ai_states, handle_ai_behaviors)
+                .chain()
                 .run_if(in_state(GameState::Game))
                 .run_if(in_state(PauseState::Running)),
         );
     }
 }
 
+// Runtime state of an enemy's `AIDef` state machine
 #[derive(Component)]
-pub struct ChaseAI {
-    pub acceleration: f32,
-}
-#[derive(Component)]
-pub struct SurroundAI {
-    pub chase_speed: f32,
-    pub surround_speed: f32,
-    pub surround_distance: f32,
-    pub clockwise: bool,
+pub struct EnemyAI {
+    state: usize,
+    // Entered on the next update, once the player's position is known
+    pending: Option<usize>,
+    behavior: BehaviorDef,
+    pub shoot: bool,
+    elapsed: f32,
+    // Orbit position or charge direction, depending on the behavior
+    angle: f32,
+    // Which way strafing circles the player
+    side: f32,
+    // Seconds left of a scripted dash overriding the state machine
+    sweeping: f32,
+    pub speed_multiplier: f32,
 }
 
-#[derive(Component)]
-struct SurroundingAI {
-    pub angle: f32,
-}
+impl EnemyAI {
+    pub fn new() -> Self {
+        EnemyAI {
+            state: 0,
+            pending: Some(0),
+            behavior: BehaviorDef::Idle,
+            shoot: false,
+            elapsed: 0.,
+            angle: 0.,
+            side: 1.,
+            sweeping: 0.,
+            speed_multiplier: 1.,
+        }
+    }
 
-pub enum EnemyAmmo {
-    Siren,
-    Wyvern,
-}
+    // Dashes along `angle` for `duration` seconds without shooting, then resumes the current state
+    pub fn sweep(&mut self, angle: f32, speed: f32, duration: f32) {
+        self.behavior = BehaviorDef::Charge { speed };
+        self.angle = angle;
+        self.shoot = false;
+        self.sweeping = duration;
+    }
 
-#[derive(Component)]
-pub struct RangedAI {
-    pub timer: Timer,
-    pub ammo: EnemyAmmo,
-}
-#[derive(Component)]
-pub struct KrakenAI {
-    pub timer: Timer,
-}
+    // Flocking only steers behaviors driven by velocity
+    pub fn flocks(&self) -> bool {
+        !matches!(
+            self.behavior,
+            BehaviorDef::Orbit { .. } | BehaviorDef::Charge { .. }
+        )
+    }
 
-#[derive(Component)]
-struct LinearProjectile {
-    angle: f32,
-    speed: f32,
+    fn enter(&mut self, index: usize, state_def: &AIStateDef, to_player: Vec2) {
+        self.state = index;
+        self.behavior = state_def.behavior;
+        self.shoot = state_def.shoot;
+        self.elapsed = 0.;
+
+        match self.behavior {
+            BehaviorDef::Orbit { .. } => self.angle = to_player.to_angle() + PI,
+            BehaviorDef::Charge { .. } => self.angle = to_player.to_angle(),
+            BehaviorDef::Strafe { .. } => {
+                self.side = if thread_rng().gen_bool(0.5) { 1. } else { -1. }
+            }
+            _ => {}
+        }
+    }
 }
 
 #[derive(Component)]
@@ -72,212 +95,111 @@ pub struct HitAnimation {
     pub last_hit: f32,
 }
 
-fn handle_chase_ai(
+fn update_ai_states(
     time: Res<Time>,
+    enemy_defs: Res<Assets<EnemyDef>>,
     player_query: Query<&Transform, With<Player>>,
-    mut chase_query: Query<(&mut Sprite, &ChaseAI, &mut Movement, &Transform), Without<Player>>,
+    mut ai_query: Query<(&mut EnemyAI, &EnemyArchetype, &Transform, &Health), Without<Player>>,
 ) {
     let player_transform = player_query.get_single().unwrap();
 
-    for (mut chase_sprite, chase_ai, mut chase_movement, chase_transform) in chase_query.iter_mut()
-    {
-        let direction = player_transform.translation.xy() - chase_transform.translation.xy();
-        let normalized = direction.normalize_or_zero();
-
-        if normalized.x < 0. {
-            chase_sprite.flip_x = true;
-        } else {
-            chase_sprite.flip_x = false;
+    for (mut ai, archetype, transform, health) in ai_query.iter_mut() {
+        let Some(enemy_def) = enemy_defs.get(&archetype.0) else {
+            continue;
         };
+        let states = &enemy_def.ai.states;
+        let to_player = player_transform.translation.xy() - transform.translation.xy();
 
-        chase_movement.velocity += normalized * time.delta_seconds() * chase_ai.acceleration;
-    }
-}
-
-fn handle_surround_ai(
-    mut commands: Commands,
-    time: Res<Time>,
-    player_query: Query<&Transform, With<Player>>,
-    mut surround_query: Query<
-        (
-            Entity,
-            &mut Transform,
-            &mut Sprite,
-            &SurroundAI,
-            Option<&mut SurroundingAI>,
-        ),
-        Without<Player>,
-    >,
-) {
-    let player_transform = player_query.get_single().unwrap();
-
-    for (entity, mut surround_transform, mut surround_sprite, surround_ai, surrounding_ai) in
-        surround_query.iter_mut()
-    {
-        if let Some(mut surrounding_ai) = surrounding_ai {
-            let angle_diff = surround_ai.surround_speed * time.delta_seconds()
-                / surround_ai.surround_distance
-                * 2.
-                * PI;
-            surrounding_ai.angle += angle_diff;
-
-            let old_pos = surround_transform.translation.xy();
-            let new_pos = player_transform.translation.xy()
-                + Vec2::from_angle(surrounding_ai.angle) * surround_ai.surround_distance;
-
-            if player_transform.translation.x < new_pos.x {
-                surround_sprite.flip_x = true;
-            } else {
-                surround_sprite.flip_x = false;
-            };
-
-            surround_transform.translation.x = new_pos.x;
-            surround_transform.translation.y = new_pos.y;
-        } else {
-            let offset = player_transform.translation.xy() - surround_transform.translation.xy();
-            let distance = offset.length();
-
-            if distance > surround_ai.surround_distance {
-                let movement = offset.normalize_or_zero() * surround_ai.chase_speed;
-
-                if movement.x < 0. {
-                    surround_sprite.flip_x = true;
-                } else {
-                    surround_sprite.flip_x = false;
-                };
-
-                surround_transform.translation += (movement * time.delta_seconds()).extend(0.);
-            } else {
-                commands.entity(entity).insert(SurroundingAI {
-                    angle: offset.to_angle() + PI,
-                });
+        if ai.sweeping > 0. {
+            ai.sweeping -= time.delta_seconds();
+            if ai.sweeping <= 0. {
+                ai.pending = Some(ai.pending.unwrap_or(ai.state));
             }
-        }
-    }
-}
-
-fn handle_ranged_ai(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    time: Res<Time>,
-    player_query: Query<&Transform, With<Player>>,
-    mut ranged_query: Query<(&mut RangedAI, &Transform), With<SurroundingAI>>,
-) {
-    let player_transform = player_query.get_single().unwrap();
-
-    for (mut attacker, ranged_transform) in ranged_query.iter_mut() {
-        attacker.timer.tick(time.delta());
-
-        if !attacker.timer.just_finished() {
             continue;
         }
 
-        let direction = (player_transform.translation.xy() - ranged_transform.translation.xy())
-            .normalize_or_zero();
-        let angle = direction.to_angle();
-
-        match attacker.ammo {
-            EnemyAmmo::Siren => {
-                commands.spawn((
-                    ContactEnemy,
-                    LinearProjectile { angle, speed: 80. },
-                    SpriteBundle {
-                        texture: asset_server.load("sprites/projectiles/siren_attack.png"),
-                        transform: Transform {
-                            translation: ranged_transform.translation,
-                            rotation: Quat::from_rotation_z(angle),
-                            ..default()
-                        },
-                        ..default()
-                    },
-                    TimedDespawn { delay: 10. },
-                    DistanceDespawn,
-                    YSort(0.),
-                    Sensor,
-                    Collider::ball(14.),
-                    EnemyKnockback { knockback: 120. },
-                    GameDespawn,
-                ));
-            }
-            EnemyAmmo::Wyvern => {
-                commands.spawn((
-                    ContactEnemy,
-                    LinearProjectile { angle, speed: 100. },
-                    SpriteBundle {
-                        texture: asset_server.load("sprites/projectiles/wyvern_fire.png"),
-                        transform: Transform {
-                            translation: ranged_transform.translation,
-                            ..default()
-                        },
-                        ..default()
-                    },
-                    TimedDespawn { delay: 10. },
-                    DistanceDespawn,
-                    YSort(0.),
-                    Sensor,
-                    Collider::ball(14.),
-                    EnemyKnockback { knockback: 300. },
-                    GameDespawn,
-                ));
-            }
+        ai.elapsed += time.delta_seconds();
+
+        let next = ai.pending.take().or_else(|| {
+            let distance = to_player.length();
+            states[ai.state]
+                .transitions
+                .iter()
+                .find(|transition| match transition.when {
+                    ConditionDef::Within(range) => distance <= range,
+                    ConditionDef::Beyond(range) => distance > range,
+                    ConditionDef::HealthBelow(fraction) => {
+                        health.health / health.max_health < fraction
+                    }
+                    ConditionDef::After(duration) => ai.elapsed >= duration,
+                })
+                .and_then(|transition| states.iter().position(|state| state.name == transition.to))
+        });
+
+        if let Some(next) = next {
+            ai.enter(next, &states[next], to_player);
         }
     }
 }
 
-const KRAKEN_WAVES: i32 = 8;
-fn handle_kraken_ai(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
+fn handle_ai_behaviors(
     time: Res<Time>,
     player_query: Query<&Transform, With<Player>>,
-    mut kraken_query: Query<(&mut KrakenAI, &Transform)>,
+    mut ai_query: Query<
+        (&mut EnemyAI, &mut Transform, &mut Movement, &mut Sprite),
+        Without<Player>,
+    >,
 ) {
     let player_transform = player_query.get_single().unwrap();
+    let player_position = player_transform.translation.xy();
+
+    for (mut ai, mut transform, mut movement, mut sprite) in ai_query.iter_mut() {
+        let offset = player_position - transform.translation.xy();
+        let direction = offset.normalize_or_zero();
+        let multiplier = ai.speed_multiplier;
+
+        match ai.behavior {
+            BehaviorDef::Idle => {}
+            BehaviorDef::Chase { acceleration } => {
+                sprite.flip_x = direction.x < 0.;
+                movement.velocity += direction * acceleration * multiplier * time.delta_seconds();
+            }
+            BehaviorDef::Approach { speed } => {
+                sprite.flip_x = direction.x < 0.;
+                transform.translation +=
+                    (direction * speed * multiplier * time.delta_seconds()).extend(0.);
+            }
+            BehaviorDef::Orbit { speed, distance } => {
+                ai.angle += speed * multiplier * time.delta_seconds() / distance * 2. * PI;
 
-    for (mut kraken, kraken_transform) in kraken_query.iter_mut() {
-        kraken.timer.tick(time.delta());
-
-        if !kraken.timer.just_finished() {
-            continue;
-        }
-
-        for i in 0..KRAKEN_WAVES {
-            let rotation = (2. * PI / KRAKEN_WAVES as f32) * i as f32;
+                let new_pos = player_position + Vec2::from_angle(ai.angle) * distance;
+                sprite.flip_x = player_position.x < new_pos.x;
 
-            commands.spawn((
-                ContactEnemy,
-                LinearProjectile {
-                    angle: rotation,
-                    speed: 100.,
-                },
-                SpriteBundle {
-                    texture: asset_server.load("sprites/projectiles/kraken_wave.png"),
-                    transform: Transform::from_translation(kraken_transform.translation),
-                    sprite: Sprite {
-                        flip_x: Vec2::from_angle(rotation).x < 0.,
-                        ..default()
-                    },
-                    ..default()
-                },
-                TimedDespawn { delay: 10. },
-                DistanceDespawn,
-                YSort(0.),
-                Sensor,
-                Collider::ball(14.),
-                EnemyKnockback { knockback: 600. },
-                GameDespawn,
-            ));
+                transform.translation.x = new_pos.x;
+                transform.translation.y = new_pos.y;
+            }
+            BehaviorDef::Flee { acceleration } => {
+                sprite.flip_x = direction.x > 0.;
+                movement.velocity -= direction * acceleration * multiplier * time.delta_seconds();
+            }
+            BehaviorDef::Strafe {
+                acceleration,
+                distance,
+            } => {
+                let tangent = direction.perp() * ai.side;
+                let correction = direction * (offset.length() - distance) / distance;
+                sprite.flip_x = direction.x < 0.;
+                movement.velocity += (tangent + correction).normalize_or_zero()
+                    * acceleration
+                    * multiplier
+                    * time.delta_seconds();
+            }
+            BehaviorDef::Charge { speed } => {
+                let charge = Vec2::from_angle(ai.angle);
+                sprite.flip_x = charge.x < 0.;
+                transform.translation +=
+                    (charge * speed * multiplier * time.delta_seconds()).extend(0.);
+            }
         }
     }
 }
-
-fn update_linear_projectiles(
-    time: Res<Time>,
-    mut projectiles_query: Query<(&mut Transform, &LinearProjectile)>,
-) {
-    for (mut transform, projectile) in projectiles_query.iter_mut() {
-        let movement = Vec2::from_angle(projectile.angle) * projectile.speed * time.delta_seconds();
-        transform.translation.x += movement.x;
-        transform.translation.y += movement.y;
-    }
-}
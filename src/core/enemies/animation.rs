@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+use bevy_animations_manager::AnimationsManager;
+use bevy_tweening::{
+    lens::{SpriteColorLens, TransformScaleLens},
+    Animator, EaseMethod, Tween,
+};
+use std::time::Duration;
+
+use crate::core::{GameState, IngameTime, PauseState, TweenDespawn};
+
+use super::{affixes::Elite, ai::HitAnimation, death::EnemyKilled, EnemyHit, Health};
+
+pub struct EnemyAnimationPlugin;
+
+impl Plugin for EnemyAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (flash_on_hit, update_enemy_animations, play_death_animations)
+                .chain()
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+pub const IDLE_ANIMATION: &str = "idle";
+pub const MOVING_ANIMATION: &str = "moving";
+pub const HIT_ANIMATION: &str = "hit";
+pub const DEATH_ANIMATION: &str = "death";
+
+pub const HIT_FLASH_DURATION: f32 = 0.1;
+// Channels past 1 saturate the texture to white
+const HIT_FLASH_COLOR: Color = Color::rgb(10., 10., 10.);
+const DEATH_DURATION: f32 = 0.5;
+// Per second, below this an enemy counts as idle
+const MOVING_THRESHOLD: f32 = 5.;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AnimationState {
+    Idle,
+    Moving,
+    Hit,
+}
+
+#[derive(Component)]
+pub struct EnemyAnimation {
+    state: AnimationState,
+    // AI behaviors move either through `Movement` or the transform directly, so track both
+    last_position: Vec2,
+    // Names of the clips the enemy's def actually has
+    clips: Vec<&'static str>,
+}
+
+impl EnemyAnimation {
+    pub fn new(position: Vec2) -> Self {
+        EnemyAnimation {
+            state: AnimationState::Idle,
+            last_position: position,
+            clips: Vec::new(),
+        }
+    }
+
+    pub fn add_clip(&mut self, name: &'static str) {
+        self.clips.push(name);
+    }
+
+    // Missing clips are skipped so the current one keeps playing
+    fn play(&self, animations_manager: &mut AnimationsManager, name: &'static str) {
+        if self.clips.contains(&name) {
+            animations_manager.play(name);
+        }
+    }
+}
+
+fn base_color(elite: Option<&Elite>) -> Color {
+    elite.map_or(Color::WHITE, |elite| elite.tint())
+}
+
+fn flash_on_hit(
+    ingame_time: Res<IngameTime>,
+    mut ev_enemy_hit: EventReader<EnemyHit>,
+    mut enemy_query: Query<
+        (
+            &mut HitAnimation,
+            &mut EnemyAnimation,
+            &mut AnimationsManager,
+            &mut Sprite,
+        ),
+        With<Health>,
+    >,
+) {
+    for event in ev_enemy_hit.read() {
+        let Ok((mut hit_animation, mut enemy_animation, mut animations_manager, mut sprite)) =
+            enemy_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        hit_animation.last_hit = ingame_time.0;
+        sprite.color = HIT_FLASH_COLOR;
+
+        if enemy_animation.state != AnimationState::Hit {
+            enemy_animation.state = AnimationState::Hit;
+            enemy_animation.play(&mut animations_manager, HIT_ANIMATION);
+        }
+    }
+}
+
+fn update_enemy_animations(
+    time: Res<Time>,
+    ingame_time: Res<IngameTime>,
+    mut enemy_query: Query<
+        (
+            &HitAnimation,
+            &mut EnemyAnimation,
+            &mut AnimationsManager,
+            &mut Sprite,
+            &Transform,
+            Option<&Elite>,
+        ),
+        With<Health>,
+    >,
+) {
+    for (
+        hit_animation,
+        mut enemy_animation,
+        mut animations_manager,
+        mut sprite,
+        transform,
+        elite,
+    ) in enemy_query.iter_mut()
+    {
+        let position = transform.translation.xy();
+        let speed = position.distance(enemy_animation.last_position)
+            / time.delta_seconds().max(f32::EPSILON);
+        enemy_animation.last_position = position;
+
+        if ingame_time.0 - hit_animation.last_hit < hit_animation.duration {
+            continue;
+        }
+
+        // Flash is over, restore whatever tint the enemy had
+        if enemy_animation.state == AnimationState::Hit {
+            sprite.color = base_color(elite);
+        }
+
+        let state = if speed > MOVING_THRESHOLD {
+            AnimationState::Moving
+        } else {
+            AnimationState::Idle
+        };
+
+        if state != enemy_animation.state {
+            enemy_animation.state = state;
+            let animation = match state {
+                AnimationState::Moving => MOVING_ANIMATION,
+                _ => IDLE_ANIMATION,
+            };
+            enemy_animation.play(&mut animations_manager, animation);
+        }
+    }
+}
+
+fn play_death_animations(
+    mut commands: Commands,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    mut dead_query: Query<(
+        &EnemyAnimation,
+        &mut AnimationsManager,
+        &mut Sprite,
+        &Transform,
+        Option<&Elite>,
+    )>,
+) {
+    for event in ev_enemy_killed.read() {
+        let Ok((enemy_animation, mut animations_manager, mut sprite, transform, elite)) =
+            dead_query.get_mut(event.entity)
+        else {
+            continue;
+        };
+
+        let color = base_color(elite);
+        sprite.color = color;
+        enemy_animation.play(&mut animations_manager, DEATH_ANIMATION);
+
+        let fade = Tween::new(
+            EaseMethod::Linear,
+            Duration::from_secs_f32(DEATH_DURATION),
+            SpriteColorLens {
+                start: color,
+                end: color.with_a(0.),
+            },
+        )
+        .with_completed_event(0);
+        let shrink = Tween::new(
+            EaseMethod::Linear,
+            Duration::from_secs_f32(DEATH_DURATION),
+            TransformScaleLens {
+                start: transform.scale,
+                end: transform.scale * 0.5,
+            },
+        );
+
+        commands.entity(event.entity).insert((
+            Animator::new(fade),
+            Animator::new(shrink),
+            TweenDespawn,
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::PI;
+
+use crate::core::{
+    environment::spawn_item_pickup, gui::ShowBanner, items::Item, player::Player, GameState,
+    IngameTime, Movement, PauseState,
+};
+
+use super::{
+    death::EnemyKilled,
+    defs::{EnemyDef, EnemyDefs},
+    emitters::Emitter,
+    spawning::{insert_attack, spawn_enemy},
+    EnemyArchetype, Health, SPAWN_DISTANCE,
+};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BossEncounters>()
+            .insert_resource(BossFight::default())
+            .add_systems(
+                Update,
+                (
+                    start_boss_encounters,
+                    spawn_bosses,
+                    update_boss_phases,
+                    handle_boss_defeat,
+                )
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(GameState::Game), reset_boss_fight);
+    }
+}
+
+const BOSS_WARNING_DURATION: f32 = 3.;
+const BOSS_DROPS: [Item; 2] = [Item::PoseidonTrident, Item::ZeusThunderbolt];
+
+#[derive(Component)]
+pub struct Boss {
+    pub name: String,
+    phase: usize,
+}
+
+struct BossEncounter {
+    time: f32,
+    name: &'static str,
+    boss: Handle<EnemyDef>,
+}
+
+#[derive(Resource)]
+struct BossEncounters(Vec<BossEncounter>);
+impl FromWorld for BossEncounters {
+    fn from_world(world: &mut World) -> Self {
+        let enemy_defs = world.resource::<EnemyDefs>();
+        BossEncounters(vec![
+            BossEncounter {
+                time: 300.,
+                name: "The Kraken",
+                boss: enemy_defs.kraken_boss.clone(),
+            },
+            BossEncounter {
+                time: 600.,
+                name: "The Lernaean Hydra",
+                boss: enemy_defs.hydra_boss.clone(),
+            },
+            BossEncounter {
+                time: 900.,
+                name: "Ladon",
+                boss: enemy_defs.wyvern_boss.clone(),
+            },
+        ])
+    }
+}
+
+#[derive(Default)]
+enum BossState {
+    #[default]
+    Idle,
+    Warning(Timer),
+    Fighting,
+}
+
+#[derive(Resource, Default)]
+pub struct BossFight {
+    next_encounter: usize,
+    state: BossState,
+}
+
+impl BossFight {
+    // Normal spawning is paused from the warning until the boss is defeated
+    pub fn in_progress(&self) -> bool {
+        !matches!(self.state, BossState::Idle)
+    }
+}
+
+fn reset_boss_fight(mut boss_fight: ResMut<BossFight>) {
+    *boss_fight = BossFight::default();
+}
+
+fn start_boss_encounters(
+    ingame_time: Res<IngameTime>,
+    boss_encounters: Res<BossEncounters>,
+    mut boss_fight: ResMut<BossFight>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    if boss_fight.in_progress() {
+        return;
+    }
+
+    let Some(encounter) = boss_encounters.0.get(boss_fight.next_encounter) else {
+        return;
+    };
+
+    if ingame_time.0 < encounter.time {
+        return;
+    }
+
+    boss_fight.state =
+        BossState::Warning(Timer::from_seconds(BOSS_WARNING_DURATION, TimerMode::Once));
+    ev_show_banner.send(ShowBanner(format!("{} approaches!", encounter.name)));
+}
+
+fn spawn_bosses(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    enemy_defs: Res<Assets<EnemyDef>>,
+    boss_encounters: Res<BossEncounters>,
+    mut boss_fight: ResMut<BossFight>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let BossState::Warning(timer) = &mut boss_fight.state else {
+        return;
+    };
+
+    timer.tick(time.delta());
+    if !timer.finished() {
+        return;
+    }
+
+    let encounter = &boss_encounters.0[boss_fight.next_encounter];
+    let Some(boss_def) = enemy_defs.get(&encounter.boss) else {
+        return;
+    };
+
+    let player_transform = player_query.get_single().unwrap();
+
+    let mut rng = thread_rng();
+    let random_angle = rng.gen_range((0.)..(2. * PI));
+    let spawn_position =
+        Vec2::from_angle(random_angle) * SPAWN_DISTANCE + player_transform.translation.xy();
+
+    let boss_entity = spawn_enemy(
+        &mut commands,
+        &asset_server,
+        &mut texture_atlas_layouts,
+        &encounter.boss,
+        boss_def,
+        spawn_position,
+    );
+
+    // Left recyclable so a boss that is outrun is brought back in front of the player
+    commands.entity(boss_entity).insert(Boss {
+        name: encounter.name.into(),
+        phase: 0,
+    });
+
+    boss_fight.state = BossState::Fighting;
+    boss_fight.next_encounter += 1;
+}
+
+fn update_boss_phases(
+    mut commands: Commands,
+    enemy_defs: Res<Assets<EnemyDef>>,
+    mut boss_query: Query<(Entity, &mut Boss, &Health, &EnemyArchetype, &mut Movement)>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    for (boss_entity, mut boss, boss_health, boss_archetype, mut boss_movement) in
+        boss_query.iter_mut()
+    {
+        let Some(boss_def) = enemy_defs.get(&boss_archetype.0) else {
+            continue;
+        };
+
+        let health_fraction = boss_health.health / boss_health.max_health;
+        let phase = boss_def
+            .phases
+            .iter()
+            .filter(|phase| health_fraction < phase.below)
+            .count();
+
+        if phase <= boss.phase {
+            continue;
+        }
+
+        boss.phase = phase;
+
+        let phase_def = &boss_def.phases[phase - 1];
+        boss_movement.max_speed = phase_def.speed;
+
+        let mut boss_commands = commands.entity(boss_entity);
+        boss_commands.remove::<Emitter>();
+        if let Some(attack) = &phase_def.attack {
+            insert_attack(&mut boss_commands, attack);
+        }
+
+        ev_show_banner.send(ShowBanner(format!("{} grows furious!", boss.name)));
+    }
+}
+
+fn handle_boss_defeat(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut boss_fight: ResMut<BossFight>,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    boss_query: Query<&Boss>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    for event in ev_enemy_killed.read() {
+        let Ok(boss) = boss_query.get(event.entity) else {
+            continue;
+        };
+
+        let mut rng = thread_rng();
+        let drop = BOSS_DROPS[rng.gen_range(0..BOSS_DROPS.len())];
+
+        spawn_item_pickup(&mut commands, &asset_server, drop, event.position);
+
+        commands.entity(event.entity).remove::<Boss>();
+
+        boss_fight.state = BossState::Idle;
+        ev_show_banner.send(ShowBanner(format!("{} has fallen!", boss.name)));
+    }
+}
@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::PI;
+
+use crate::core::{wind::Drift, GameState, IngameTime, Movement, PauseState, YSort};
+
+use super::{
+    affixes::{Affix, Elite},
+    defs::{DeathDef, EnemyDef},
+    orbs::orb_texture,
+    pools::EntityPool,
+    spawning::spawn_enemy,
+    DamageKind, EnemyArchetype, EnemyXp, Health, OrbParts, XpOrb, BIG_ORB, ORB_DRIFT, SMALL_ORB,
+};
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (drop_xp_orbs, split_on_death)
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+// Sent once per kill while the dying entity is still fading out, so hooks can read its components
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub kind: DamageKind,
+}
+
+#[derive(Component)]
+pub struct SplitGeneration(pub u32);
+
+fn drop_xp_orbs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ingame_time: Res<IngameTime>,
+    mut orb_pool: ResMut<EntityPool<OrbParts>>,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    enemy_xp_query: Query<&EnemyXp>,
+) {
+    for event in ev_enemy_killed.read() {
+        let Ok(enemy_xp) = enemy_xp_query.get(event.entity) else {
+            continue;
+        };
+
+        let big_xp = (enemy_xp.0 / BIG_ORB) as i32;
+        let small_xp = (enemy_xp.0 / SMALL_ORB) as i32 - big_xp * (BIG_ORB as i32);
+        for _ in 0..big_xp {
+            let mut rng = thread_rng();
+            let direction = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
+
+            // Expiry is left to orb consolidation so no XP is lost
+            let orb = orb_pool.acquire(&mut commands, None);
+            commands.entity(orb).insert((
+                XpOrb {
+                    value: BIG_ORB,
+                    dropped_at: ingame_time.0,
+                },
+                SpriteBundle {
+                    texture: asset_server.load(orb_texture(BIG_ORB)),
+                    transform: Transform::from_translation(event.position.extend(0.)),
+                    ..default()
+                },
+                Movement {
+                    max_speed: 1000.,
+                    velocity: direction * rng.gen_range(25.0..75.0),
+                    friction: 0.8,
+                },
+                Drift(ORB_DRIFT),
+                YSort(0.),
+            ));
+        }
+        for _ in 0..small_xp {
+            let mut rng = thread_rng();
+            let direction = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
+
+            // Expiry is left to orb consolidation so no XP is lost
+            let orb = orb_pool.acquire(&mut commands, None);
+            commands.entity(orb).insert((
+                XpOrb {
+                    value: SMALL_ORB,
+                    dropped_at: ingame_time.0,
+                },
+                SpriteBundle {
+                    texture: asset_server.load(orb_texture(SMALL_ORB)),
+                    transform: Transform::from_translation(event.position.extend(0.)),
+                    ..default()
+                },
+                Movement {
+                    max_speed: 1000.,
+                    velocity: direction * rng.gen_range(25.0..75.0),
+                    friction: 0.8,
+                },
+                Drift(ORB_DRIFT),
+                YSort(0.),
+            ));
+        }
+    }
+}
+
+const SPLIT_OFFSET: f32 = 24.;
+
+// The affix keeps an archetype's own split rules, so Greek Fire still stops a Splitting hydra
+fn affix_split(on_death: Option<DeathDef>) -> DeathDef {
+    let (max_generation, prevented_by) = match on_death {
+        Some(DeathDef::Split {
+            max_generation,
+            prevented_by,
+            ..
+        }) => (max_generation, prevented_by),
+        None => (1, None),
+    };
+
+    DeathDef::Split {
+        count: 2,
+        max_generation,
+        health_scale: 0.5,
+        xp_scale: 0.5,
+        speed_scale: 1.2,
+        size_scale: 0.75,
+        prevented_by,
+    }
+}
+
+fn split_on_death(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    enemy_defs: Res<Assets<EnemyDef>>,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    dead_query: Query<(&EnemyArchetype, Option<&SplitGeneration>, Option<&Elite>)>,
+) {
+    for event in ev_enemy_killed.read() {
+        let Ok((enemy_archetype, split_generation, elite)) = dead_query.get(event.entity) else {
+            continue;
+        };
+
+        let Some(enemy_def) = enemy_defs.get(&enemy_archetype.0) else {
+            continue;
+        };
+
+        let split = match elite {
+            Some(elite) if elite.has(Affix::Splitting) => Some(affix_split(enemy_def.on_death)),
+            _ => enemy_def.on_death,
+        };
+
+        let Some(DeathDef::Split {
+            count,
+            max_generation,
+            health_scale,
+            xp_scale,
+            speed_scale,
+            size_scale,
+            prevented_by,
+        }) = split
+        else {
+            continue;
+        };
+
+        if prevented_by == Some(event.kind) {
+            continue;
+        }
+
+        let generation = split_generation.map_or(0, |split_generation| split_generation.0) + 1;
+        if generation > max_generation {
+            continue;
+        }
+
+        let health = enemy_def.health * health_scale.powi(generation as i32);
+        let xp = enemy_def.xp * xp_scale.powi(generation as i32);
+        let speed = enemy_def.speed * speed_scale.powi(generation as i32);
+        let scale = enemy_def.scale * size_scale.powi(generation as i32);
+
+        let mut rng = thread_rng();
+        let split_angle = rng.gen_range((0.)..(2. * PI));
+
+        for i in 0..count {
+            let angle = split_angle + (2. * PI / count as f32) * i as f32;
+            let spawn_position = event.position + Vec2::from_angle(angle) * SPLIT_OFFSET;
+
+            let child = spawn_enemy(
+                &mut commands,
+                &asset_server,
+                &mut texture_atlas_layouts,
+                &enemy_archetype.0,
+                enemy_def,
+                spawn_position,
+            );
+
+            commands.entity(child).insert((
+                SplitGeneration(generation),
+                Health {
+                    health,
+                    max_health: health,
+                },
+                EnemyXp(xp),
+                Movement {
+                    velocity: Vec2::ZERO,
+                    friction: 1.,
+                    max_speed: speed,
+                },
+                Transform {
+                    translation: spawn_position.extend(0.),
+                    scale: Vec3::splat(scale),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+use super::DamageKind;
+
+pub struct EnemyDefsPlugin;
+
+impl Plugin for EnemyDefsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EnemyDef>()
+            .register_asset_loader(EnemyDefLoader)
+            .init_resource::<EnemyDefs>();
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct EnemyDef {
+    pub texture: String,
+    pub health: f32,
+    pub xp: f32,
+    pub speed: f32,
+    pub collider: ColliderDef,
+    pub knockback: f32,
+    // Whether touching the enemy itself hurts the player
+    pub contact: bool,
+    pub ai: AIDef,
+    #[serde(default)]
+    pub attack: Option<AttackDef>,
+    pub animation: AnimationDef,
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    // Listed from the highest health threshold to the lowest
+    #[serde(default)]
+    pub phases: Vec<PhaseDef>,
+    #[serde(default)]
+    pub on_death: Option<DeathDef>,
+    #[serde(default)]
+    pub flocking: Option<FlockingDef>,
+    // Each entry is rolled on its own for every kill
+    #[serde(default)]
+    pub loot: Vec<LootDef>,
+}
+
+fn default_scale() -> f32 {
+    1.
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum ColliderDef {
+    Ball { radius: f32 },
+    CapsuleX { half_length: f32, radius: f32 },
+    CapsuleY { half_length: f32, radius: f32 },
+}
+
+impl ColliderDef {
+    pub fn collider(&self) -> Collider {
+        match *self {
+            ColliderDef::Ball { radius } => Collider::ball(radius),
+            ColliderDef::CapsuleX {
+                half_length,
+                radius,
+            } => Collider::capsule_x(half_length, radius),
+            ColliderDef::CapsuleY {
+                half_length,
+                radius,
+            } => Collider::capsule_y(half_length, radius),
+        }
+    }
+
+    // Rough size used to slide along solids
+    pub fn radius(&self) -> f32 {
+        match *self {
+            ColliderDef::Ball { radius } => radius,
+            ColliderDef::CapsuleX {
+                half_length,
+                radius,
+            }
+            | ColliderDef::CapsuleY {
+                half_length,
+                radius,
+            } => radius + half_length / 2.,
+        }
+    }
+}
+
+// A state machine; the first state is entered on spawn
+#[derive(Deserialize, Clone)]
+pub struct AIDef {
+    pub states: Vec<AIStateDef>,
+}
+
+impl AIDef {
+    // Caught at load time, the state machine indexes states directly
+    fn validate(&self) -> Result<(), String> {
+        if self.states.is_empty() {
+            return Err("AI needs at least one state".into());
+        }
+        for state in self.states.iter() {
+            for transition in state.transitions.iter() {
+                if !self.states.iter().any(|other| other.name == transition.to) {
+                    return Err(format!(
+                        "AI state \"{}\" transitions to unknown state \"{}\"",
+                        state.name, transition.to
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AIStateDef {
+    pub name: String,
+    pub behavior: BehaviorDef,
+    // Whether the enemy's attack runs while in this state
+    #[serde(default)]
+    pub shoot: bool,
+    // Checked in order, the first satisfied one is taken
+    #[serde(default)]
+    pub transitions: Vec<TransitionDef>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum BehaviorDef {
+    Idle,
+    // Accelerates toward the player
+    Chase { acceleration: f32 },
+    // Moves straight at the player at a fixed speed, ignoring momentum
+    Approach { speed: f32 },
+    // Circles the player at `distance`, starting from wherever the state was entered
+    Orbit { speed: f32, distance: f32 },
+    Flee { acceleration: f32 },
+    // Sidesteps around the player while drifting back toward `distance`
+    Strafe { acceleration: f32, distance: f32 },
+    // Dashes along the direction to the player taken when the state was entered
+    Charge { speed: f32 },
+}
+
+#[derive(Deserialize, Clone)]
+pub struct TransitionDef {
+    pub to: String,
+    pub when: ConditionDef,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum ConditionDef {
+    // Distance to the player
+    Within(f32),
+    Beyond(f32),
+    // Fraction of max health
+    HealthBelow(f32),
+    // Seconds spent in the current state
+    After(f32),
+}
+
+// Steering weights against neighbors within `radius`, applied while chasing
+#[derive(Deserialize, Clone, Copy)]
+pub struct FlockingDef {
+    pub radius: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+// Every attack waits `cooldown`, telegraphs for `windup`, fires its pattern, then recovers for `recover`
+#[derive(Deserialize, Clone)]
+pub struct AttackDef {
+    pub cooldown: f32,
+    pub windup: f32,
+    pub recover: f32,
+    pub pattern: PatternDef,
+    pub ammo: AmmoDef,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum PatternDef {
+    // `count` shots fanned across `spread` radians around the direction to the player
+    Aimed {
+        count: i32,
+        spread: f32,
+    },
+    // `count` shots evenly spread in every direction, turned `spin` radians further each volley
+    Ring {
+        count: i32,
+        spin: f32,
+    },
+    // `count` shots fired `interval` seconds apart, each turned `step` radians from the last
+    Spiral {
+        count: i32,
+        step: f32,
+        interval: f32,
+    },
+}
+
+impl PatternDef {
+    // Directions of every shot in a volley, in firing order
+    pub fn angles(&self, aim: f32, spin: f32) -> Vec<f32> {
+        match *self {
+            PatternDef::Aimed { count, spread } => {
+                if count <= 1 {
+                    return vec![aim];
+                }
+                (0..count)
+                    .map(|i| aim - spread / 2. + spread * i as f32 / (count - 1) as f32)
+                    .collect()
+            }
+            PatternDef::Ring { count, .. } => (0..count)
+                .map(|i| spin + (2. * PI / count as f32) * i as f32)
+                .collect(),
+            PatternDef::Spiral { count, step, .. } => {
+                (0..count).map(|i| aim + step * i as f32).collect()
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PhaseDef {
+    // Fraction of max health below which this phase starts
+    pub below: f32,
+    pub speed: f32,
+    pub attack: Option<AttackDef>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum DeathDef {
+    // Splits into `count` weaker copies, each generation scaling the base stats again
+    Split {
+        count: i32,
+        max_generation: u32,
+        health_scale: f32,
+        xp_scale: f32,
+        speed_scale: f32,
+        size_scale: f32,
+        prevented_by: Option<DamageKind>,
+    },
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LootDrop {
+    // A random part or upgrade
+    Chest,
+    // Pulls in every orb around
+    Magnet,
+    // Restores the last part lost to a hit
+    RepairKit,
+    // A short boon from one of the gods
+    Blessing,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct LootDef {
+    pub drop: LootDrop,
+    // Before elite and boss multipliers
+    pub chance: f32,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AmmoOrientation {
+    Fixed,
+    Rotate,
+    FlipX,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+pub enum MotionDef {
+    #[default]
+    Linear,
+    // Turns toward the player at up to `turn_rate` radians per second for `duration` seconds
+    Homing {
+        turn_rate: f32,
+        duration: f32,
+    },
+    // Arcs onto a marked spot as far away as the player was, only hurting where it lands
+    Lobbed {
+        flight_time: f32,
+        height: f32,
+    },
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AmmoDef {
+    pub texture: String,
+    pub speed: f32,
+    pub knockback: f32,
+    pub radius: f32,
+    pub orientation: AmmoOrientation,
+    #[serde(default)]
+    pub motion: MotionDef,
+}
+
+// Missing clips fall back to `idle`
+#[derive(Deserialize, Clone)]
+pub struct AnimationDef {
+    pub frame_width: f32,
+    pub frame_height: f32,
+    pub idle: ClipDef,
+    #[serde(default)]
+    pub moving: Option<ClipDef>,
+    #[serde(default)]
+    pub hit: Option<ClipDef>,
+    #[serde(default)]
+    pub death: Option<ClipDef>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ClipDef {
+    // Defaults to the enemy's texture
+    #[serde(default)]
+    pub texture: Option<String>,
+    pub columns: usize,
+    pub rows: usize,
+    pub frame_count: usize,
+    pub frame_durations: Vec<u32>,
+}
+
+#[derive(Resource)]
+pub struct EnemyDefs {
+    pub ferris: Handle<EnemyDef>,
+    pub serpent: Handle<EnemyDef>,
+    pub siren: Handle<EnemyDef>,
+    pub elite_serpent: Handle<EnemyDef>,
+    pub hydra: Handle<EnemyDef>,
+    pub wyvern: Handle<EnemyDef>,
+    pub kraken: Handle<EnemyDef>,
+    pub kraken_boss: Handle<EnemyDef>,
+    pub hydra_boss: Handle<EnemyDef>,
+    pub wyvern_boss: Handle<EnemyDef>,
+}
+
+impl FromWorld for EnemyDefs {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        EnemyDefs {
+            ferris: asset_server.load("enemies/ferris.enemy.ron"),
+            serpent: asset_server.load("enemies/serpent.enemy.ron"),
+            siren: asset_server.load("enemies/siren.enemy.ron"),
+            elite_serpent: asset_server.load("enemies/elite_serpent.enemy.ron"),
+            hydra: asset_server.load("enemies/hydra.enemy.ron"),
+            wyvern: asset_server.load("enemies/wyvern.enemy.ron"),
+            kraken: asset_server.load("enemies/kraken.enemy.ron"),
+            kraken_boss: asset_server.load("enemies/kraken_boss.enemy.ron"),
+            hydra_boss: asset_server.load("enemies/hydra_boss.enemy.ron"),
+            wyvern_boss: asset_server.load("enemies/wyvern_boss.enemy.ron"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct EnemyDefLoader;
+
+impl AssetLoader for EnemyDefLoader {
+    type Asset = EnemyDef;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<EnemyDef, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let enemy_def = ron::de::from_bytes::<EnemyDef>(&bytes)?;
+            enemy_def.ai.validate()?;
+            Ok(enemy_def)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemy.ron"]
+    }
+}
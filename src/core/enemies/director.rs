@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::core::{
+    items::{Inventory, INVENTORY_SIZE},
+    player::{Leveling, PlayerHit},
+    GameState, IngameTime, PauseState,
+};
+
+use super::{bosses::BossFight, death::EnemyKilled, recycling::EnemyCount};
+
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Director::default())
+            .add_systems(
+                Update,
+                (track_director_events, update_director)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                Update,
+                toggle_classic_mode.run_if(in_state(GameState::StartMenu)),
+            )
+            .add_systems(OnEnter(GameState::Game), reset_director);
+    }
+}
+
+// Performance is judged over this many trailing seconds
+const PERFORMANCE_WINDOW: f32 = 60.;
+const TARGET_KILLS_PER_MINUTE: f32 = 120.;
+// Hits within the window at which the player counts as struggling
+const HIT_TOLERANCE: f32 = 3.;
+// Levels per second a typical run gains
+const EXPECTED_LEVEL_RATE: f32 = 1. / 30.;
+
+const MIN_SPAWN_MULTIPLIER: f32 = 0.6;
+const MAX_SPAWN_MULTIPLIER: f32 = 1.6;
+const MIN_ELITE_MULTIPLIER: f32 = 0.5;
+const MAX_ELITE_MULTIPLIER: f32 = 2.;
+// Per second, so difficulty drifts instead of jumping
+const ADJUST_RATE: f32 = 0.1;
+
+// Intensity tracks live enemies and recent hits
+const INTENSITY_ENEMIES: f32 = 300.;
+const INTENSITY_HIT_WINDOW: f32 = 15.;
+const INTENSITY_HITS: f32 = 2.;
+const INTENSITY_RATE: f32 = 0.5;
+
+const RELIEF_THRESHOLD: f32 = 0.85;
+const RELIEF_DURATION: f32 = 12.;
+const RELIEF_COOLDOWN: f32 = 45.;
+const RELIEF_SPAWN_MULTIPLIER: f32 = 0.35;
+
+#[derive(Resource)]
+pub struct Director {
+    // Off is classic mode, spawning straight from the spawn tables
+    pub enabled: bool,
+    intensity: f32,
+    spawn_multiplier: f32,
+    elite_multiplier: f32,
+    kills: VecDeque<f32>,
+    hits: VecDeque<f32>,
+    relief: f32,
+    relief_cooldown: f32,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Director {
+            enabled: true,
+            intensity: 0.,
+            spawn_multiplier: 1.,
+            elite_multiplier: 1.,
+            kills: VecDeque::new(),
+            hits: VecDeque::new(),
+            relief: 0.,
+            relief_cooldown: 0.,
+        }
+    }
+}
+
+impl Director {
+    // How hectic the run is right now, from 0 to 1
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn in_relief(&self) -> bool {
+        self.enabled && self.relief > 0.
+    }
+
+    pub fn spawn_multiplier(&self) -> f32 {
+        if !self.enabled {
+            1.
+        } else if self.in_relief() {
+            RELIEF_SPAWN_MULTIPLIER
+        } else {
+            self.spawn_multiplier
+        }
+    }
+
+    pub fn elite_multiplier(&self) -> f32 {
+        if !self.enabled {
+            1.
+        } else if self.in_relief() {
+            0.
+        } else {
+            self.elite_multiplier
+        }
+    }
+}
+
+fn reset_director(mut director: ResMut<Director>) {
+    *director = Director {
+        enabled: director.enabled,
+        ..default()
+    };
+}
+
+fn toggle_classic_mode(keys: Res<ButtonInput<KeyCode>>, mut director: ResMut<Director>) {
+    if keys.just_pressed(KeyCode::KeyC) {
+        director.enabled = !director.enabled;
+    }
+}
+
+fn track_director_events(
+    ingame_time: Res<IngameTime>,
+    mut director: ResMut<Director>,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    mut ev_player_hit: EventReader<PlayerHit>,
+) {
+    for _ in ev_enemy_killed.read() {
+        director.kills.push_back(ingame_time.0);
+    }
+    for _ in ev_player_hit.read() {
+        director.hits.push_back(ingame_time.0);
+    }
+
+    let window_start = ingame_time.0 - PERFORMANCE_WINDOW;
+    while director
+        .kills
+        .front()
+        .is_some_and(|kill| *kill < window_start)
+    {
+        director.kills.pop_front();
+    }
+    while director.hits.front().is_some_and(|hit| *hit < window_start) {
+        director.hits.pop_front();
+    }
+}
+
+fn update_director(
+    time: Res<Time>,
+    ingame_time: Res<IngameTime>,
+    inventory: Res<Inventory>,
+    boss_fight: Res<BossFight>,
+    mut director: ResMut<Director>,
+    leveling_query: Query<&Leveling>,
+    enemy_count: Res<EnemyCount>,
+) {
+    let delta = time.delta_seconds();
+
+    // Each score runs from -1 (struggling) to 1 (cruising)
+    let elapsed = ingame_time.0.clamp(10., PERFORMANCE_WINDOW);
+    let kills_per_minute = director.kills.len() as f32 / elapsed * 60.;
+    let kills_score = (kills_per_minute / TARGET_KILLS_PER_MINUTE - 1.).clamp(-1., 1.);
+    let hits_score = (1. - 2. * director.hits.len() as f32 / HIT_TOLERANCE).clamp(-1., 1.);
+    let inventory_score = 2. * inventory.0.len() as f32 / INVENTORY_SIZE as f32 - 1.;
+    let level_score = leveling_query.get_single().map_or(0., |leveling| {
+        let expected_level = 1. + ingame_time.0 * EXPECTED_LEVEL_RATE;
+        (leveling.level as f32 / expected_level - 1.).clamp(-1., 1.)
+    });
+    let performance = (kills_score + hits_score + inventory_score + level_score) / 4.;
+
+    let target_spawn = if performance > 0. {
+        1. + performance * (MAX_SPAWN_MULTIPLIER - 1.)
+    } else {
+        1. + performance * (1. - MIN_SPAWN_MULTIPLIER)
+    };
+    let target_elite = if performance > 0. {
+        1. + performance * (MAX_ELITE_MULTIPLIER - 1.)
+    } else {
+        1. + performance * (1. - MIN_ELITE_MULTIPLIER)
+    };
+    director.spawn_multiplier +=
+        (target_spawn - director.spawn_multiplier).clamp(-ADJUST_RATE * delta, ADJUST_RATE * delta);
+    director.elite_multiplier +=
+        (target_elite - director.elite_multiplier).clamp(-ADJUST_RATE * delta, ADJUST_RATE * delta);
+
+    let recent_hits = director
+        .hits
+        .iter()
+        .filter(|hit| ingame_time.0 - **hit < INTENSITY_HIT_WINDOW)
+        .count();
+    let pressure = if boss_fight.in_progress() {
+        1.
+    } else {
+        0.5 * (enemy_count.0 as f32 / INTENSITY_ENEMIES).min(1.)
+            + 0.5 * (recent_hits as f32 / INTENSITY_HITS).min(1.)
+    };
+    director.intensity += (pressure - director.intensity) * (INTENSITY_RATE * delta).min(1.);
+
+    director.relief = (director.relief - delta).max(0.);
+    director.relief_cooldown = (director.relief_cooldown - delta).max(0.);
+
+    // Ease off once the peak has passed, bosses count as a peak
+    if director.intensity >= RELIEF_THRESHOLD
+        && director.relief_cooldown <= 0.
+        && !boss_fight.in_progress()
+    {
+        director.relief = RELIEF_DURATION;
+        director.relief_cooldown = RELIEF_COOLDOWN;
+    }
+}
@@ -0,0 +1,537 @@
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_rapier2d::prelude::*;
+use std::{f32::consts::PI, time::Duration};
+
+use crate::core::{
+    obstacles::{hits_solid, sensor_groups},
+    player::Player,
+    wind::Drift,
+    GameDespawn, GameState, PauseState, TimedDespawn, YSort,
+};
+
+use super::{
+    ai::EnemyAI,
+    defs::{AmmoDef, AmmoOrientation, AttackDef, MotionDef, PatternDef},
+    pools::{release, EntityPool, PoolPlugin},
+    ContactEnemy, EnemyKnockback,
+};
+
+pub struct EmittersPlugin;
+
+impl Plugin for EmittersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PoolPlugin::<ProjectileParts>::default())
+            .add_systems(
+                Update,
+                (
+                    handle_emitters,
+                    update_linear_projectiles,
+                    update_homing_projectiles,
+                    update_lobbed_projectiles,
+                    stop_projectiles_on_solids,
+                )
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+const TELEGRAPH_LENGTH: f32 = 160.;
+const TELEGRAPH_RING_LENGTH: f32 = 96.;
+const TELEGRAPH_WIDTH: f32 = 2.;
+const TELEGRAPH_COLOR: Color = Color::rgba(1., 0.2, 0.2, 0.6);
+const LOB_MARKER_COLOR: Color = Color::rgba(1., 0.2, 0.2, 0.4);
+const LOB_IMPACT_DURATION: f32 = 0.2;
+const PROJECTILE_LIFETIME: f32 = 10.;
+const SOLID_STOP_RADIUS: f32 = 4.;
+const PROJECTILE_DRIFT: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AttackPhase {
+    Cooldown,
+    Windup,
+    Recover,
+}
+
+pub enum AttackStep {
+    Waiting,
+    WindupStarted,
+    Fire,
+}
+
+// Cooldown -> windup (telegraphed) -> fire -> recover -> cooldown
+pub struct AttackCycle {
+    timer: Timer,
+    cooldown: f32,
+    windup: f32,
+    recover: f32,
+    pub phase: AttackPhase,
+}
+
+impl AttackCycle {
+    pub fn new(cooldown: f32, windup: f32, recover: f32) -> Self {
+        AttackCycle {
+            timer: Timer::from_seconds(cooldown, TimerMode::Once),
+            cooldown,
+            windup,
+            recover,
+            phase: AttackPhase::Cooldown,
+        }
+    }
+
+    pub fn tick(&mut self, delta: Duration) -> AttackStep {
+        self.timer.tick(delta);
+
+        if !self.timer.finished() {
+            return AttackStep::Waiting;
+        }
+
+        match self.phase {
+            AttackPhase::Cooldown => {
+                self.start(AttackPhase::Windup, self.windup);
+                AttackStep::WindupStarted
+            }
+            AttackPhase::Windup => {
+                self.start(AttackPhase::Recover, self.recover);
+                AttackStep::Fire
+            }
+            AttackPhase::Recover => {
+                self.start(AttackPhase::Cooldown, self.cooldown);
+                AttackStep::Waiting
+            }
+        }
+    }
+
+    fn start(&mut self, phase: AttackPhase, duration: f32) {
+        self.phase = phase;
+        self.timer.set_duration(Duration::from_secs_f32(duration));
+        self.timer.reset();
+    }
+}
+
+// Shots of a spiral still waiting to be fired
+struct Burst {
+    angles: Vec<f32>,
+    fired: usize,
+    timer: Timer,
+}
+
+// Fires an `AttackDef` pattern whenever the enemy's AI state allows shooting
+#[derive(Component)]
+pub struct Emitter {
+    cycle: AttackCycle,
+    pattern: PatternDef,
+    ammo: AmmoDef,
+    // Locked in when the windup starts so shots follow the telegraph
+    aim: f32,
+    range: f32,
+    // Accumulated by rotating rings
+    spin: f32,
+    burst: Option<Burst>,
+}
+
+impl Emitter {
+    pub fn new(attack: &AttackDef) -> Self {
+        Emitter {
+            cycle: AttackCycle::new(attack.cooldown, attack.windup, attack.recover),
+            pattern: attack.pattern,
+            ammo: attack.ammo.clone(),
+            aim: 0.,
+            range: 0.,
+            spin: 0.,
+            burst: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct AttackTelegraph;
+
+#[derive(Component)]
+struct LinearProjectile {
+    angle: f32,
+    speed: f32,
+}
+
+#[derive(Component)]
+struct HomingProjectile {
+    angle: f32,
+    speed: f32,
+    turn_rate: f32,
+    remaining: f32,
+    rotate: bool,
+}
+
+// Harmless in flight, the marker becomes the hitbox on landing
+#[derive(Component)]
+struct LobbedProjectile {
+    start: Vec2,
+    target: Vec2,
+    elapsed: f32,
+    flight_time: f32,
+    height: f32,
+    marker: Entity,
+    radius: f32,
+    knockback: f32,
+}
+
+// Stripped from projectiles returned to their pool
+type ProjectileParts = (
+    LinearProjectile,
+    HomingProjectile,
+    LobbedProjectile,
+    ContactEnemy,
+    Sensor,
+    Collider,
+    EnemyKnockback,
+    Drift,
+);
+
+fn handle_emitters(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut projectile_pool: ResMut<EntityPool<ProjectileParts>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut emitter_query: Query<(Entity, &mut Emitter, &Transform, &EnemyAI), Without<Player>>,
+) {
+    let player_transform = player_query.get_single().unwrap();
+
+    for (entity, mut emitter, emitter_transform, ai) in emitter_query.iter_mut() {
+        if !ai.shoot {
+            continue;
+        }
+
+        let emitter = &mut *emitter;
+
+        if let Some(burst) = &mut emitter.burst {
+            burst.timer.tick(time.delta());
+            for _ in 0..burst.timer.times_finished_this_tick() {
+                spawn_enemy_projectile(
+                    &mut commands,
+                    &mut projectile_pool,
+                    &asset_server,
+                    &emitter.ammo,
+                    emitter_transform.translation,
+                    burst.angles[burst.fired],
+                    emitter.range,
+                );
+                burst.fired += 1;
+                if burst.fired == burst.angles.len() {
+                    break;
+                }
+            }
+            if burst.fired == burst.angles.len() {
+                emitter.burst = None;
+            }
+        }
+
+        match emitter.cycle.tick(time.delta()) {
+            AttackStep::Waiting => {}
+            AttackStep::WindupStarted => {
+                let offset = player_transform.translation.xy() - emitter_transform.translation.xy();
+                emitter.aim = offset.to_angle();
+                emitter.range = offset.length();
+
+                let length = match emitter.pattern {
+                    PatternDef::Aimed { .. } => TELEGRAPH_LENGTH,
+                    _ => TELEGRAPH_RING_LENGTH,
+                };
+
+                spawn_telegraph(
+                    &mut commands,
+                    entity,
+                    emitter_transform,
+                    emitter.cycle.windup,
+                    &emitter.pattern.angles(emitter.aim, emitter.spin),
+                    length,
+                );
+            }
+            AttackStep::Fire => {
+                let angles = emitter.pattern.angles(emitter.aim, emitter.spin);
+
+                match emitter.pattern {
+                    PatternDef::Spiral { interval, .. } if !angles.is_empty() => {
+                        spawn_enemy_projectile(
+                            &mut commands,
+                            &mut projectile_pool,
+                            &asset_server,
+                            &emitter.ammo,
+                            emitter_transform.translation,
+                            angles[0],
+                            emitter.range,
+                        );
+                        emitter.burst = Some(Burst {
+                            angles,
+                            fired: 1,
+                            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+                        });
+                    }
+                    _ => {
+                        for angle in angles {
+                            spawn_enemy_projectile(
+                                &mut commands,
+                                &mut projectile_pool,
+                                &asset_server,
+                                &emitter.ammo,
+                                emitter_transform.translation,
+                                angle,
+                                emitter.range,
+                            );
+                        }
+                    }
+                }
+
+                if let PatternDef::Ring { spin, .. } = emitter.pattern {
+                    emitter.spin += spin;
+                }
+            }
+        }
+    }
+}
+
+// Warning lines along each firing direction, parented to the attacker and gone once the windup ends
+fn spawn_telegraph(
+    commands: &mut Commands,
+    attacker: Entity,
+    attacker_transform: &Transform,
+    windup: f32,
+    angles: &[f32],
+    length: f32,
+) {
+    if windup <= 0. {
+        return;
+    }
+
+    // Undo the attacker's scale so bosses don't get thicker lines
+    let scale = attacker_transform.scale.recip();
+
+    commands.entity(attacker).with_children(|parent| {
+        for angle in angles {
+            parent.spawn((
+                AttackTelegraph,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: TELEGRAPH_COLOR,
+                        custom_size: Some(Vec2::new(length, TELEGRAPH_WIDTH)),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(0., 0., -0.01),
+                        rotation: Quat::from_rotation_z(*angle),
+                        scale,
+                    },
+                    ..default()
+                },
+                TimedDespawn { delay: windup },
+            ));
+        }
+    });
+}
+
+fn spawn_enemy_projectile(
+    commands: &mut Commands,
+    projectile_pool: &mut EntityPool<ProjectileParts>,
+    asset_server: &Res<AssetServer>,
+    ammo: &AmmoDef,
+    position: Vec3,
+    angle: f32,
+    range: f32,
+) {
+    let rotation = match ammo.orientation {
+        AmmoOrientation::Rotate => Quat::from_rotation_z(angle),
+        _ => Quat::IDENTITY,
+    };
+
+    let projectile = projectile_pool.acquire(commands, Some(PROJECTILE_LIFETIME));
+    commands.entity(projectile).insert((
+        SpriteBundle {
+            texture: asset_server.load(&ammo.texture),
+            transform: Transform {
+                translation: position,
+                rotation,
+                ..default()
+            },
+            sprite: Sprite {
+                flip_x: ammo.orientation == AmmoOrientation::FlipX
+                    && Vec2::from_angle(angle).x < 0.,
+                ..default()
+            },
+            ..default()
+        },
+        YSort(0.),
+    ));
+
+    match ammo.motion {
+        MotionDef::Linear => {
+            commands.entity(projectile).insert(LinearProjectile {
+                angle,
+                speed: ammo.speed,
+            });
+        }
+        MotionDef::Homing {
+            turn_rate,
+            duration,
+        } => {
+            commands.entity(projectile).insert(HomingProjectile {
+                angle,
+                speed: ammo.speed,
+                turn_rate,
+                remaining: duration,
+                rotate: ammo.orientation == AmmoOrientation::Rotate,
+            });
+        }
+        MotionDef::Lobbed {
+            flight_time,
+            height,
+        } => {
+            let start = position.xy();
+            let target = start + Vec2::from_angle(angle) * range;
+
+            let marker = commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: LOB_MARKER_COLOR,
+                            custom_size: Some(Vec2::splat(ammo.radius * 2.)),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: target.extend(0.),
+                            rotation: Quat::from_rotation_z(PI / 4.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    YSort(-1.),
+                    TimedDespawn {
+                        delay: flight_time + LOB_IMPACT_DURATION,
+                    },
+                    GameDespawn,
+                ))
+                .id();
+
+            commands.entity(projectile).insert(LobbedProjectile {
+                start,
+                target,
+                elapsed: 0.,
+                flight_time,
+                height,
+                marker,
+                radius: ammo.radius,
+                knockback: ammo.knockback,
+            });
+            return;
+        }
+    }
+
+    commands.entity(projectile).insert((
+        ContactEnemy,
+        Sensor,
+        sensor_groups(),
+        Collider::ball(ammo.radius),
+        EnemyKnockback {
+            knockback: ammo.knockback,
+        },
+        Drift(PROJECTILE_DRIFT),
+    ));
+}
+
+fn update_linear_projectiles(
+    time: Res<Time>,
+    mut projectiles_query: Query<(&mut Transform, &LinearProjectile)>,
+) {
+    for (mut transform, projectile) in projectiles_query.iter_mut() {
+        let movement = Vec2::from_angle(projectile.angle) * projectile.speed * time.delta_seconds();
+        transform.translation.x += movement.x;
+        transform.translation.y += movement.y;
+    }
+}
+
+fn update_homing_projectiles(
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    mut projectiles_query: Query<(&mut Transform, &mut HomingProjectile), Without<Player>>,
+) {
+    let player_transform = player_query.get_single().unwrap();
+
+    for (mut transform, mut projectile) in projectiles_query.iter_mut() {
+        if projectile.remaining > 0. {
+            projectile.remaining -= time.delta_seconds();
+
+            let desired =
+                (player_transform.translation.xy() - transform.translation.xy()).to_angle();
+            let difference =
+                Vec2::from_angle(projectile.angle).angle_between(Vec2::from_angle(desired));
+            let max_turn = projectile.turn_rate * time.delta_seconds();
+            projectile.angle += difference.clamp(-max_turn, max_turn);
+
+            if projectile.rotate {
+                transform.rotation = Quat::from_rotation_z(projectile.angle);
+            }
+        }
+
+        let movement = Vec2::from_angle(projectile.angle) * projectile.speed * time.delta_seconds();
+        transform.translation.x += movement.x;
+        transform.translation.y += movement.y;
+    }
+}
+
+fn update_lobbed_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectiles_query: Query<(Entity, &mut Transform, &mut LobbedProjectile)>,
+    mut marker_query: Query<&mut Sprite>,
+) {
+    for (entity, mut transform, mut projectile) in projectiles_query.iter_mut() {
+        projectile.elapsed += time.delta_seconds();
+        let progress = (projectile.elapsed / projectile.flight_time).min(1.);
+
+        let position = projectile.start.lerp(projectile.target, progress)
+            + Vec2::Y * projectile.height * 4. * progress * (1. - progress);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+
+        if progress < 1. {
+            continue;
+        }
+
+        release::<ProjectileParts>(&mut commands, entity);
+
+        let Ok(mut marker_sprite) = marker_query.get_mut(projectile.marker) else {
+            continue;
+        };
+        marker_sprite.color = LOB_MARKER_COLOR.with_a(1.);
+
+        commands.entity(projectile.marker).insert((
+            ContactEnemy,
+            Sensor,
+            sensor_groups(),
+            Collider::ball(projectile.radius),
+            EnemyKnockback {
+                knockback: projectile.knockback,
+            },
+        ));
+    }
+}
+
+// Lobbed projectiles fly over solids
+fn stop_projectiles_on_solids(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    projectiles_query: Query<
+        (Entity, &Transform),
+        Or<(With<LinearProjectile>, With<HomingProjectile>)>,
+    >,
+) {
+    for (entity, transform) in projectiles_query.iter() {
+        if hits_solid(
+            &rapier_context,
+            transform.translation.xy(),
+            SOLID_STOP_RADIUS,
+        ) {
+            release::<ProjectileParts>(&mut commands, entity);
+        }
+    }
+}
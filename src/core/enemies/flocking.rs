@@ -0,0 +1,128 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::core::{GameState, Movement, PauseState};
+
+use super::{ai::EnemyAI, defs::FlockingDef};
+
+pub struct FlockingPlugin;
+
+impl Plugin for FlockingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FlockGrid::default()).add_systems(
+            Update,
+            (update_flock_grid, apply_flocking)
+                .chain()
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+const FLOCK_CELL_SIZE: f32 = 64.;
+
+#[derive(Component)]
+pub struct Flocking(pub FlockingDef);
+
+struct FlockMember {
+    entity: Entity,
+    position: Vec2,
+    velocity: Vec2,
+}
+
+// Buckets every flocking enemy by cell so neighbor lookups only scan nearby cells
+#[derive(Resource, Default)]
+pub struct FlockGrid(HashMap<IVec2, Vec<FlockMember>>);
+
+fn grid_cell(position: Vec2) -> IVec2 {
+    (position / FLOCK_CELL_SIZE).floor().as_ivec2()
+}
+
+fn update_flock_grid(
+    mut flock_grid: ResMut<FlockGrid>,
+    flock_query: Query<(Entity, &Transform, &Movement), With<Flocking>>,
+) {
+    // Keep last frame's occupied cells to reuse their allocations, drop the rest
+    flock_grid.0.retain(|_, cell| !cell.is_empty());
+    for cell in flock_grid.0.values_mut() {
+        cell.clear();
+    }
+
+    for (entity, transform, movement) in flock_query.iter() {
+        let position = transform.translation.xy();
+        flock_grid
+            .0
+            .entry(grid_cell(position))
+            .or_default()
+            .push(FlockMember {
+                entity,
+                position,
+                velocity: movement.velocity,
+            });
+    }
+}
+
+fn apply_flocking(
+    time: Res<Time>,
+    flock_grid: Res<FlockGrid>,
+    mut flock_query: Query<(Entity, &Flocking, &Transform, &mut Movement, &EnemyAI)>,
+) {
+    for (entity, flocking, transform, mut movement, ai) in flock_query.iter_mut() {
+        if !ai.flocks() {
+            continue;
+        }
+
+        let flocking = flocking.0;
+        let position = transform.translation.xy();
+        let cell = grid_cell(position);
+        let cell_range = (flocking.radius / FLOCK_CELL_SIZE).ceil() as i32;
+
+        let mut separation = Vec2::ZERO;
+        let mut velocity_sum = Vec2::ZERO;
+        let mut position_sum = Vec2::ZERO;
+        let mut neighbors = 0;
+
+        for x in (cell.x - cell_range)..=(cell.x + cell_range) {
+            for y in (cell.y - cell_range)..=(cell.y + cell_range) {
+                let Some(members) = flock_grid.0.get(&IVec2::new(x, y)) else {
+                    continue;
+                };
+
+                for member in members {
+                    if member.entity == entity {
+                        continue;
+                    }
+
+                    let offset = position - member.position;
+                    let distance = offset.length();
+                    if distance > flocking.radius {
+                        continue;
+                    }
+
+                    // Stacked enemies get pushed apart in an arbitrary but stable direction
+                    let away = if distance > 0. {
+                        offset / distance
+                    } else {
+                        Vec2::from_angle(entity.index() as f32)
+                    };
+                    separation += away * (1. - distance / flocking.radius);
+                    velocity_sum += member.velocity;
+                    position_sum += member.position;
+                    neighbors += 1;
+                }
+            }
+        }
+
+        if neighbors == 0 {
+            continue;
+        }
+
+        let alignment = velocity_sum / neighbors as f32 - movement.velocity;
+        let cohesion = (position_sum / neighbors as f32 - position) / flocking.radius;
+
+        let steering = separation * flocking.separation
+            + alignment * flocking.alignment
+            + cohesion * flocking.cohesion;
+
+        movement.velocity += steering * time.delta_seconds();
+    }
+}
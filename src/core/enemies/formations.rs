@@ -0,0 +1,238 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    thread_rng, Rng,
+};
+use std::f32::consts::PI;
+
+use crate::core::{player::Player, GameState, IngameTime, Movement, PauseState};
+
+use super::{
+    affixes::Elite, ai::EnemyAI, bosses::BossFight, defs::EnemyDef, director::Director,
+    recycling::EnemyCount, spawning::spawn_enemy, EnemySpawnTables, ENTITY_LIMIT, SPAWN_DISTANCE,
+};
+
+pub struct FormationsPlugin;
+
+impl Plugin for FormationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FormationSchedule::default())
+            .add_systems(
+                Update,
+                spawn_formations
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(GameState::Game), reset_formation_schedule);
+    }
+}
+
+// Seconds between attempts to spawn a formation
+const FORMATION_INTERVAL: f32 = 20.;
+const WALL_SPACING: f32 = 48.;
+const PACK_SPACING: f32 = 40.;
+const AMBUSH_SPREAD: f32 = 96.;
+// Sweeping formations keep going this far past the player before falling back to their AI
+const SWEEP_OVERSHOOT: f32 = 1.5;
+
+#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Formation {
+    // Surrounds the player on every side
+    Ring,
+    // A line on one side sweeping straight across
+    Wall,
+    // A V led from the front, dashing at the player
+    Pack,
+    // A cluster waiting ahead of where the player is sailing
+    Ambush,
+}
+
+#[derive(PartialEq, Eq, Clone)]
+pub struct FormationRate {
+    formation: Formation,
+    enemy_type: Handle<EnemyDef>,
+    count: i32,
+    weight: i32,
+    // Seconds before the same formation can appear again
+    cooldown: u32,
+}
+
+impl FormationRate {
+    pub fn new(
+        formation: Formation,
+        enemy_type: Handle<EnemyDef>,
+        count: i32,
+        weight: i32,
+        cooldown: u32,
+    ) -> Self {
+        FormationRate {
+            formation,
+            enemy_type,
+            count,
+            weight,
+            cooldown,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct FormationSchedule {
+    next_attempt: f32,
+    // When each formation is off cooldown
+    ready_at: HashMap<Formation, f32>,
+}
+
+impl Default for FormationSchedule {
+    fn default() -> Self {
+        FormationSchedule {
+            next_attempt: FORMATION_INTERVAL,
+            ready_at: HashMap::new(),
+        }
+    }
+}
+
+fn reset_formation_schedule(mut formation_schedule: ResMut<FormationSchedule>) {
+    *formation_schedule = FormationSchedule::default();
+}
+
+fn spawn_formations(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    ingame_time: Res<IngameTime>,
+    enemy_spawn_tables: Res<EnemySpawnTables>,
+    enemy_defs: Res<Assets<EnemyDef>>,
+    boss_fight: Res<BossFight>,
+    director: Res<Director>,
+    mut formation_schedule: ResMut<FormationSchedule>,
+    player_query: Query<(&Transform, &Movement), With<Player>>,
+    enemy_count: Res<EnemyCount>,
+) {
+    if ingame_time.0 < formation_schedule.next_attempt {
+        return;
+    }
+    formation_schedule.next_attempt = ingame_time.0 + FORMATION_INTERVAL;
+
+    if boss_fight.in_progress() || director.in_relief() {
+        return;
+    }
+
+    let Some(enemy_spawn_table) = enemy_spawn_tables.0.get(&(ingame_time.0 as i32)) else {
+        return;
+    };
+
+    let ready: Vec<&FormationRate> = enemy_spawn_table
+        .formations
+        .iter()
+        .filter(|rate| {
+            formation_schedule
+                .ready_at
+                .get(&rate.formation)
+                .map_or(true, |ready_at| ingame_time.0 >= *ready_at)
+        })
+        .collect();
+
+    let Ok(formation_weights) = WeightedIndex::new(ready.iter().map(|rate| rate.weight)) else {
+        return;
+    };
+
+    let mut rng = thread_rng();
+    let rate = ready[formation_weights.sample(&mut rng)];
+
+    if enemy_count.0 + rate.count as usize > ENTITY_LIMIT {
+        return;
+    }
+
+    let Some(enemy_def) = enemy_defs.get(&rate.enemy_type) else {
+        return;
+    };
+
+    formation_schedule
+        .ready_at
+        .insert(rate.formation, ingame_time.0 + rate.cooldown as f32);
+
+    let (player_transform, player_movement) = player_query.get_single().unwrap();
+    let player_position = player_transform.translation.xy();
+    let side = rng.gen_range((0.)..(2. * PI));
+
+    // Positions paired with an optional sweep direction
+    let placements: Vec<(Vec2, Option<f32>)> = match rate.formation {
+        Formation::Ring => (0..rate.count)
+            .map(|i| {
+                let angle = side + 2. * PI * i as f32 / rate.count as f32;
+                (
+                    player_position + Vec2::from_angle(angle) * SPAWN_DISTANCE,
+                    None,
+                )
+            })
+            .collect(),
+        Formation::Wall => {
+            let center = player_position + Vec2::from_angle(side) * SPAWN_DISTANCE;
+            let along = Vec2::from_angle(side).perp();
+            (0..rate.count)
+                .map(|i| {
+                    let offset = (i as f32 - (rate.count - 1) as f32 / 2.) * WALL_SPACING;
+                    (center + along * offset, Some(side + PI))
+                })
+                .collect()
+        }
+        Formation::Pack => {
+            let leader = player_position + Vec2::from_angle(side) * SPAWN_DISTANCE;
+            let back = Vec2::from_angle(side);
+            let along = back.perp();
+            (0..rate.count)
+                .map(|i| {
+                    let row = ((i + 1) / 2) as f32;
+                    let wing = if i % 2 == 0 { 1. } else { -1. };
+                    (
+                        leader + (back + along * wing) * row * PACK_SPACING,
+                        Some(side + PI),
+                    )
+                })
+                .collect()
+        }
+        Formation::Ambush => {
+            let heading = if player_movement.velocity.length_squared() > 1. {
+                player_movement.velocity.normalize()
+            } else {
+                Vec2::from_angle(side)
+            };
+            let center = player_position + heading * SPAWN_DISTANCE;
+            (0..rate.count)
+                .map(|_| {
+                    let scatter = Vec2::from_angle(rng.gen_range((0.)..(2. * PI)))
+                        * rng.gen_range((0.)..AMBUSH_SPREAD);
+                    (center + scatter, None)
+                })
+                .collect()
+        }
+    };
+
+    for (spawn_position, sweep) in placements {
+        let enemy = spawn_enemy(
+            &mut commands,
+            &asset_server,
+            &mut texture_atlas_layouts,
+            &rate.enemy_type,
+            enemy_def,
+            spawn_position,
+        );
+
+        if let Some(angle) = sweep {
+            let mut ai = EnemyAI::new();
+            ai.sweep(
+                angle,
+                enemy_def.speed,
+                SPAWN_DISTANCE * SWEEP_OVERSHOOT / enemy_def.speed,
+            );
+            commands.entity(enemy).insert(ai);
+        }
+
+        let affixes = enemy_spawn_table
+            .elites
+            .roll(&mut rng, director.elite_multiplier());
+        if !affixes.is_empty() {
+            commands.entity(enemy).insert(Elite { affixes });
+        }
+    }
+}
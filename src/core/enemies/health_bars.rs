@@ -0,0 +1,197 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::core::{gui::ShowBanner, GameState, IngameTime, PauseState};
+
+use super::{affixes::Elite, bosses::Boss, Enemy, EnemyHit, Health};
+
+pub struct HealthBarsPlugin;
+
+impl Plugin for HealthBarsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HealthBarMode::DamagedOnly).add_systems(
+            Update,
+            (
+                cycle_health_bar_mode,
+                attach_health_bars,
+                update_health_bars,
+            )
+                .chain()
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+const BAR_WIDTH: f32 = 24.;
+const BAR_HEIGHT: f32 = 3.;
+// In the enemy's local space, so bigger enemies carry their bar higher
+const BAR_OFFSET: f32 = 20.;
+const BAR_LINGER: f32 = 3.;
+const BAR_FADE: f32 = 0.5;
+const BAR_BACKGROUND: Color = Color::rgb(0.1, 0.1, 0.1);
+const BAR_FILL: Color = Color::rgb(0.8, 0.1, 0.1);
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub enum HealthBarMode {
+    Off,
+    // Elites and bosses always show their bar regardless
+    DamagedOnly,
+    Always,
+}
+
+// Lives on the enemy, pointing at its bar children
+#[derive(Component)]
+pub struct HealthBar {
+    background: Entity,
+    fill: Entity,
+    last_hit: f32,
+}
+
+fn cycle_health_bar_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut health_bar_mode: ResMut<HealthBarMode>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    if !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+
+    let (mode, label) = match *health_bar_mode {
+        HealthBarMode::Off => (HealthBarMode::DamagedOnly, "damaged only"),
+        HealthBarMode::DamagedOnly => (HealthBarMode::Always, "always"),
+        HealthBarMode::Always => (HealthBarMode::Off, "off"),
+    };
+    *health_bar_mode = mode;
+    ev_show_banner.send(ShowBanner(format!("Health bars: {}", label)));
+}
+
+fn attach_health_bars(
+    mut commands: Commands,
+    ingame_time: Res<IngameTime>,
+    health_bar_mode: Res<HealthBarMode>,
+    mut ev_enemy_hit: EventReader<EnemyHit>,
+    mut hit_query: Query<(Entity, Option<&mut HealthBar>), (With<Enemy>, With<Health>)>,
+    enemy_query: Query<
+        (Entity, &Transform, Option<&Elite>, Option<&Boss>),
+        (With<Enemy>, With<Health>, Without<HealthBar>),
+    >,
+) {
+    if *health_bar_mode == HealthBarMode::Off {
+        ev_enemy_hit.clear();
+        for (enemy_entity, health_bar) in hit_query.iter() {
+            let Some(health_bar) = health_bar else {
+                continue;
+            };
+            commands.entity(health_bar.background).despawn_recursive();
+            commands.entity(enemy_entity).remove::<HealthBar>();
+        }
+        return;
+    }
+
+    // Only hits that got through, a shield soaking one leaves the bar alone
+    let mut hit = Vec::new();
+    for event in ev_enemy_hit.read() {
+        match hit_query.get_mut(event.entity) {
+            Ok((_, Some(mut health_bar))) => health_bar.last_hit = ingame_time.0,
+            Ok((_, None)) => hit.push(event.entity),
+            Err(_) => {}
+        }
+    }
+
+    for (enemy_entity, enemy_transform, elite, boss) in enemy_query.iter() {
+        let always = *health_bar_mode == HealthBarMode::Always || elite.is_some() || boss.is_some();
+        if !always && !hit.contains(&enemy_entity) {
+            continue;
+        }
+
+        let mut fill = Entity::PLACEHOLDER;
+        let background = commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: BAR_BACKGROUND,
+                    custom_size: Some(Vec2::new(BAR_WIDTH, BAR_HEIGHT)),
+                    ..default()
+                },
+                // Undo the enemy's scale so every bar is the same size
+                transform: Transform {
+                    translation: Vec3::new(0., BAR_OFFSET, 0.01),
+                    scale: enemy_transform.scale.recip(),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                fill = parent
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: BAR_FILL,
+                            custom_size: Some(Vec2::new(BAR_WIDTH, BAR_HEIGHT)),
+                            anchor: Anchor::CenterLeft,
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(-BAR_WIDTH / 2., 0., 0.01),
+                        ..default()
+                    })
+                    .id();
+            })
+            .id();
+
+        commands
+            .entity(enemy_entity)
+            .add_child(background)
+            .insert(HealthBar {
+                background,
+                fill,
+                last_hit: ingame_time.0,
+            });
+    }
+}
+
+fn update_health_bars(
+    mut commands: Commands,
+    ingame_time: Res<IngameTime>,
+    health_bar_mode: Res<HealthBarMode>,
+    enemy_query: Query<(
+        &Health,
+        &HealthBar,
+        &Transform,
+        Option<&Elite>,
+        Option<&Boss>,
+    )>,
+    dead_query: Query<(Entity, &HealthBar), Without<Health>>,
+    mut sprite_query: Query<(&mut Sprite, &mut Visibility, &mut Transform), Without<HealthBar>>,
+) {
+    for (health, health_bar, enemy_transform, elite, boss) in enemy_query.iter() {
+        let always = *health_bar_mode == HealthBarMode::Always || elite.is_some() || boss.is_some();
+        let since_hit = ingame_time.0 - health_bar.last_hit;
+        let alpha = if always {
+            1.
+        } else {
+            ((BAR_LINGER + BAR_FADE - since_hit) / BAR_FADE).clamp(0., 1.)
+        };
+        let fraction = (health.health / health.max_health).clamp(0., 1.);
+
+        if let Ok((mut sprite, mut visibility, mut transform)) =
+            sprite_query.get_mut(health_bar.background)
+        {
+            // Affixes can grow an enemy after its bar was attached
+            transform.scale = enemy_transform.scale.recip();
+            sprite.color.set_a(alpha);
+            *visibility = if alpha > 0. {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+        if let Ok((mut sprite, _, _)) = sprite_query.get_mut(health_bar.fill) {
+            sprite.color.set_a(alpha);
+            sprite.custom_size = Some(Vec2::new(BAR_WIDTH * fraction, BAR_HEIGHT));
+        }
+    }
+
+    // Dying enemies fade out without their bar
+    for (enemy_entity, health_bar) in dead_query.iter() {
+        commands.entity(health_bar.background).despawn_recursive();
+        commands.entity(enemy_entity).remove::<HealthBar>();
+    }
+}
@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::PI;
+
+use crate::core::{
+    gui::ShowBanner,
+    items::{Inventory, Item, INVENTORY_SIZE},
+    obstacles::sensor_groups,
+    player::{Leveling, LostParts, Player, StatIncrease},
+    DistanceDespawn, GameDespawn, GameState, GameStats, PauseState, TimedDespawn, YSort,
+};
+
+use super::{
+    affixes::Elite,
+    bosses::Boss,
+    death::EnemyKilled,
+    defs::{EnemyDef, LootDrop},
+    EnemyArchetype, Magnetized, XpOrb,
+};
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Blessing>()
+            .add_systems(
+                Update,
+                (drop_loot, handle_loot_pickups, update_blessings)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(GameState::Game), reset_blessings);
+    }
+}
+
+// Each affix adds this much to the base chance
+const ELITE_LOOT_BONUS: f32 = 2.;
+const BOSS_LOOT_MULTIPLIER: f32 = 5.;
+const LOOT_SCATTER: f32 = 24.;
+const LOOT_LIFETIME: f32 = 60.;
+
+const CHEST_PARTS: [Item; 5] = [
+    Item::Spear,
+    Item::Bow,
+    Item::GreekFire,
+    Item::PoseidonTrident,
+    Item::ZeusThunderbolt,
+];
+const CHEST_UPGRADE_CHANCE: f32 = 0.5;
+const CHEST_UPGRADE: f32 = 0.1;
+
+const BLESSING_DURATION: f32 = 20.;
+const BLESSING_BONUS: f32 = 0.5;
+
+#[derive(Component)]
+pub struct LootPickup(LootDrop);
+
+// Temporary bonus on top of the player's leveling, taken back off when it runs out.
+// A single record updated in place, so pickups and expiry in the same frame never lose track of it
+#[derive(Resource, Default)]
+struct Blessing {
+    remaining: f32,
+    damage: f32,
+    rate: f32,
+}
+
+fn loot_sprite(drop: LootDrop) -> (Color, Vec2) {
+    match drop {
+        LootDrop::Chest => (Color::rgb(0.9, 0.7, 0.2), Vec2::new(24., 18.)),
+        LootDrop::Magnet => (Color::rgb(0.9, 0.2, 0.2), Vec2::splat(16.)),
+        LootDrop::RepairKit => (Color::rgb(0.3, 0.9, 0.4), Vec2::splat(16.)),
+        LootDrop::Blessing => (Color::rgb(0.6, 0.85, 1.), Vec2::splat(14.)),
+    }
+}
+
+fn drop_loot(
+    mut commands: Commands,
+    enemy_defs: Res<Assets<EnemyDef>>,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    dead_query: Query<(&EnemyArchetype, Option<&Elite>, Option<&Boss>)>,
+) {
+    for event in ev_enemy_killed.read() {
+        let Ok((enemy_archetype, elite, boss)) = dead_query.get(event.entity) else {
+            continue;
+        };
+        let Some(enemy_def) = enemy_defs.get(&enemy_archetype.0) else {
+            continue;
+        };
+
+        let mut multiplier = 1.;
+        if let Some(elite) = elite {
+            multiplier += ELITE_LOOT_BONUS * elite.affixes.len() as f32;
+        }
+        if boss.is_some() {
+            multiplier *= BOSS_LOOT_MULTIPLIER;
+        }
+
+        let mut rng = thread_rng();
+
+        for loot in enemy_def.loot.iter() {
+            if !rng.gen_bool((loot.chance * multiplier).clamp(0., 1.).into()) {
+                continue;
+            }
+
+            let position = event.position
+                + Vec2::from_angle(rng.gen_range((0.)..(2. * PI)))
+                    * rng.gen_range((0.)..LOOT_SCATTER);
+            let (color, size) = loot_sprite(loot.drop);
+            let rotation = if loot.drop == LootDrop::Blessing {
+                Quat::from_rotation_z(PI / 4.)
+            } else {
+                Quat::IDENTITY
+            };
+
+            commands.spawn((
+                LootPickup(loot.drop),
+                Collider::ball(20.),
+                Sensor,
+                sensor_groups(),
+                ActiveEvents::COLLISION_EVENTS,
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: position.extend(0.),
+                        rotation,
+                        ..default()
+                    },
+                    ..default()
+                },
+                YSort(0.),
+                TimedDespawn {
+                    delay: LOOT_LIFETIME,
+                },
+                DistanceDespawn,
+                GameDespawn,
+            ));
+        }
+    }
+}
+
+fn handle_loot_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut inventory: ResMut<Inventory>,
+    mut lost_parts: ResMut<LostParts>,
+    mut game_stats: ResMut<GameStats>,
+    loot_query: Query<(&LootPickup, Entity)>,
+    mut blessing: ResMut<Blessing>,
+    mut player_query: Query<&mut Leveling, With<Player>>,
+    orb_query: Query<Entity, With<XpOrb>>,
+    mut ev_stat_increase: EventWriter<StatIncrease>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity_one, entity_two, _) = collision_event else {
+            continue;
+        };
+        let entities = [entity_one, entity_two];
+        let mut maybe_loot = loot_query.iter_many(entities);
+        let mut maybe_player = player_query.iter_many_mut(entities);
+
+        let (Some((loot, loot_entity)), Some(mut leveling)) =
+            (maybe_loot.fetch_next(), maybe_player.fetch_next())
+        else {
+            continue;
+        };
+
+        commands.entity(loot_entity).despawn_recursive();
+        let mut rng = thread_rng();
+
+        match loot.0 {
+            LootDrop::Chest => {
+                if rng.gen_bool(CHEST_UPGRADE_CHANCE.into()) {
+                    if rng.gen_bool(0.5) {
+                        leveling.damage_multiplier += CHEST_UPGRADE;
+                        ev_stat_increase.send(StatIncrease("DMG++".into()));
+                    } else {
+                        leveling.rate_multiplier += CHEST_UPGRADE;
+                        ev_stat_increase.send(StatIncrease("SPD++".into()));
+                    }
+                } else {
+                    inventory
+                        .0
+                        .push_back(CHEST_PARTS[rng.gen_range(0..CHEST_PARTS.len())]);
+                    if inventory.0.len() > INVENTORY_SIZE {
+                        inventory.0.pop_front();
+                    }
+                    game_stats.items_collected += 1;
+                }
+            }
+            LootDrop::Magnet => {
+                for orb in orb_query.iter() {
+                    commands.entity(orb).insert(Magnetized);
+                }
+            }
+            LootDrop::RepairKit => {
+                // A full hull has nothing to repair, the part stays lost
+                if inventory.0.len() < INVENTORY_SIZE {
+                    if let Some(part) = lost_parts.0.pop() {
+                        inventory.0.push_back(part);
+                        ev_stat_increase.send(StatIncrease("REPAIRED".into()));
+                    }
+                }
+            }
+            LootDrop::Blessing => {
+                // A new blessing replaces the old one instead of stacking
+                leveling.damage_multiplier -= blessing.damage;
+                leveling.rate_multiplier -= blessing.rate;
+
+                let (god, damage, rate) = if rng.gen_bool(0.5) {
+                    ("Zeus", BLESSING_BONUS, 0.)
+                } else {
+                    ("Poseidon", 0., BLESSING_BONUS)
+                };
+                leveling.damage_multiplier += damage;
+                leveling.rate_multiplier += rate;
+
+                *blessing = Blessing {
+                    remaining: BLESSING_DURATION,
+                    damage,
+                    rate,
+                };
+                ev_show_banner.send(ShowBanner(format!("Blessed by {}", god)));
+            }
+        }
+    }
+}
+
+fn update_blessings(
+    time: Res<Time>,
+    mut blessing: ResMut<Blessing>,
+    mut player_query: Query<&mut Leveling, With<Player>>,
+) {
+    if blessing.remaining <= 0. {
+        return;
+    }
+    blessing.remaining -= time.delta_seconds();
+    if blessing.remaining > 0. {
+        return;
+    }
+
+    for mut leveling in player_query.iter_mut() {
+        leveling.damage_multiplier -= blessing.damage;
+        leveling.rate_multiplier -= blessing.rate;
+    }
+    *blessing = Blessing::default();
+}
+
+// Leveling is reset with the player, so only the record needs clearing
+fn reset_blessings(mut blessing: ResMut<Blessing>) {
+    *blessing = Blessing::default();
+}
@@ -1,44 +1,92 @@
-use crate::core::items::behaviors::ContactWeapon;
 use bevy::prelude::*;
 use bevy_kira_audio::{AudioChannel, AudioControl};
-use bevy_rapier2d::prelude::*;
-use bevy_tweening::{lens::SpriteColorLens, Animator, EaseMethod, Tween};
 use rand::{
     distributions::{Distribution, WeightedIndex},
     thread_rng, Rng,
 };
 use rangemap::{range_map, RangeMap};
-use std::{collections::HashSet, f32::consts::PI, time::Duration};
-
-use self::ai::{AIPlugin, ChaseAI, KrakenAI, RangedAI, SurroundAI};
+use serde::Deserialize;
+use std::{collections::HashMap, f32::consts::PI};
+
+use self::{
+    affixes::{AffixesPlugin, Elite, EliteRolls, Shield},
+    ai::{AIPlugin, EnemyAI},
+    animation::EnemyAnimationPlugin,
+    bosses::{BossFight, BossPlugin},
+    death::{DeathPlugin, EnemyKilled},
+    defs::{EnemyDef, EnemyDefs, EnemyDefsPlugin},
+    director::{Director, DirectorPlugin},
+    emitters::{Emitter, EmittersPlugin},
+    flocking::FlockingPlugin,
+    formations::{Formation, FormationRate, FormationsPlugin},
+    health_bars::HealthBarsPlugin,
+    loot::LootPlugin,
+    orbs::{OrbsPlugin, OverflowGem},
+    pools::{release, PoolPlugin},
+    recycling::{EnemyCount, RecyclingPlugin},
+};
 
 use super::{
     audio::{play_sfx, SFXChannel, SFXQueue},
-    items::behaviors::{HomingBehavior, HomingFlip},
+    biomes::{Biome, CurrentBiome},
     player::{Player, XpGained},
-    DistanceDespawn, GameDespawn, GameStats, IngameTime, Movement, TimedDespawn, TweenDespawn,
-    YSort,
+    weather::Weather,
+    wind::Drift,
+    DistanceDespawn, GameStats, IngameTime, Movement, TimedDespawn,
 };
 use crate::core::{GameState, PauseState};
 
+pub mod affixes;
 mod ai;
+mod animation;
+pub mod bosses;
+pub mod death;
+pub mod defs;
+pub mod director;
+mod emitters;
+mod flocking;
+mod formations;
+pub mod health_bars;
+pub mod loot;
+mod orbs;
+mod pools;
+mod recycling;
 mod spawning;
 
 pub struct EnemiesPlugin;
 
 impl Plugin for EnemiesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(AIPlugin)
-            .insert_resource(LastSpawn(0.))
-            .insert_resource(EnemySpawnTables::default())
-            .add_event::<DamageEvent>()
-            .add_systems(
-                Update,
-                (spawn_enemies, spawn_blahaj, damage_enemies, update_xp_orbs)
-                    .run_if(in_state(GameState::Game))
-                    .run_if(in_state(PauseState::Running)),
-            )
-            .add_systems(OnEnter(GameState::Game), handle_game_cleanup);
+        app.add_plugins((
+            AIPlugin,
+            EnemyDefsPlugin,
+            BossPlugin,
+            DeathPlugin,
+            FlockingPlugin,
+            AffixesPlugin,
+            HealthBarsPlugin,
+            EnemyAnimationPlugin,
+            EmittersPlugin,
+            DirectorPlugin,
+            FormationsPlugin,
+            RecyclingPlugin,
+            LootPlugin,
+            OrbsPlugin,
+            PoolPlugin::<OrbParts>::default(),
+        ))
+        .insert_resource(LastSpawn(0.))
+        .init_resource::<EnemySpawnTables>()
+        .init_resource::<BiomeSpawnOverrides>()
+        .add_event::<DamageEvent>()
+        .add_event::<EnemyKilled>()
+        .add_event::<EnemyHit>()
+        .add_systems(
+            Update,
+            (spawn_enemies, damage_enemies, update_xp_orbs)
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        )
+        .add_systems(OnEnter(GameState::Game), handle_game_cleanup);
     }
 }
 
@@ -51,6 +99,9 @@ pub struct Enemy;
 #[derive(Component)]
 pub struct EnemyXp(f32);
 
+#[derive(Component)]
+pub struct EnemyArchetype(pub Handle<EnemyDef>);
+
 #[derive(Component)]
 pub struct Targetable;
 
@@ -58,24 +109,25 @@ pub struct Targetable;
 pub struct DamageEvent {
     pub damage: f32,
     pub entity: Entity,
+    pub kind: DamageKind,
 }
 
-const SPAWN_DISTANCE: f32 = 800.;
-const ENTITY_LIMIT: usize = 2500;
-const BLAHAJ_SPAWN_CHANCE: f32 = 0.005;
-// const BLAHAJ_SPAWN_CHANCE: f32 = 1.;
+// Sent for damage that got through, a hit absorbed by a shield sends nothing
+#[derive(Event)]
+pub struct EnemyHit {
+    pub entity: Entity,
+}
 
-#[derive(PartialEq, Eq, Clone)]
-enum EnemyType {
-    Ferris,
-    Serpent,
-    Siren,
-    EliteSerpent,
-    Hydra,
-    Wyvern,
-    Kraken,
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DamageKind {
+    Physical,
+    Fire,
+    Lightning,
 }
 
+const SPAWN_DISTANCE: f32 = 800.;
+const ENTITY_LIMIT: usize = 2500;
+
 #[derive(Component)]
 pub struct ContactEnemy;
 
@@ -86,7 +138,7 @@ pub struct EnemyKnockback {
 
 #[derive(PartialEq, Eq, Clone)]
 struct EnemyRate {
-    enemy_type: EnemyType,
+    enemy_type: Handle<EnemyDef>,
     weight: i32,
 }
 
@@ -99,122 +151,252 @@ pub struct Health {
 #[derive(PartialEq, Eq, Clone)]
 struct EnemySpawnTable {
     global_rate: i32,
+    elites: EliteRolls,
     enemy_rates: Vec<EnemyRate>,
+    formations: Vec<FormationRate>,
 }
 
 #[derive(Resource)]
 struct EnemySpawnTables(RangeMap<i32, EnemySpawnTable>);
-impl EnemySpawnTables {
-    fn default() -> Self {
+impl FromWorld for EnemySpawnTables {
+    fn from_world(world: &mut World) -> Self {
+        let enemy_defs = world.resource::<EnemyDefs>();
         EnemySpawnTables(range_map! {
             0..60 => EnemySpawnTable {
                 global_rate: 150,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Serpent, weight: 2}, EnemyRate { enemy_type: EnemyType::Siren, weight: 1}],
+                elites: EliteRolls::none(),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.serpent.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.siren.clone(), weight: 1}],
+                formations: vec![],
             },
             60..120 => EnemySpawnTable {
                 global_rate: 200,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Serpent, weight: 2}, EnemyRate { enemy_type: EnemyType::Siren, weight: 1}],
+                elites: EliteRolls::none(),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.serpent.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.siren.clone(), weight: 1}],
+                formations: vec![],
             },
             120..180 => EnemySpawnTable {
                 global_rate: 250,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Serpent, weight: 2}, EnemyRate { enemy_type: EnemyType::Siren, weight: 2}, EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 1}],
+                elites: EliteRolls::basic(2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.serpent.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.siren.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.serpent.clone(), 12, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.serpent.clone(), 7, 3, 40)],
             },
             180..240 => EnemySpawnTable {
                 global_rate: 350,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 1}, EnemyRate { enemy_type: EnemyType::Siren, weight: 1}],
+                elites: EliteRolls::basic(2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 1}, EnemyRate { enemy_type: enemy_defs.siren.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.serpent.clone(), 12, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.serpent.clone(), 7, 3, 40)],
             },
             240..300=> EnemySpawnTable {
                 global_rate: 375,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 20}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 6}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 4}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(4, 1),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 20}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 6}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 14, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 10, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.hydra.clone(), 4, 1, 50)],
             },
             300..360=> EnemySpawnTable {
                 global_rate: 400,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 10}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 4}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(4, 1),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 10}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 14, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 10, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.hydra.clone(), 4, 1, 50)],
             },
             360..420=> EnemySpawnTable {
                 global_rate: 425,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 2}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(4, 1),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 14, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 10, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.hydra.clone(), 4, 1, 50)],
             },
             420..480=> EnemySpawnTable {
                 global_rate: 450,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(4, 1),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 14, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 10, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.hydra.clone(), 4, 1, 50)],
             },
             480..540=> EnemySpawnTable {
                 global_rate: 475,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(6, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 18, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 14, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.siren.clone(), 8, 1, 50)],
             },
             540..600=> EnemySpawnTable {
                 global_rate: 500,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(6, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 18, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 14, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.siren.clone(), 8, 1, 50)],
             },
             600..660=> EnemySpawnTable {
                 global_rate: 550,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(6, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 18, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 14, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.siren.clone(), 8, 1, 50)],
             },
             660..720=> EnemySpawnTable {
                 global_rate: 600,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(6, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 18, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 9, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.elite_serpent.clone(), 14, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.siren.clone(), 8, 1, 50)],
             },
             720..780=> EnemySpawnTable {
                 global_rate: 650,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             780..840=> EnemySpawnTable {
                 global_rate: 700,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             840..900=> EnemySpawnTable {
                 global_rate: 750,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             900..960=> EnemySpawnTable  {
                 global_rate: 800,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             960..1020=> EnemySpawnTable  {
                 global_rate: 850,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1020..1080=> EnemySpawnTable  {
                 global_rate: 900,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1080..1140=> EnemySpawnTable  {
                 global_rate: 950,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1140..1200=> EnemySpawnTable  {
                 global_rate: 1000,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::EliteSerpent, weight: 4}, EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(8, 2),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.elite_serpent.clone(), weight: 4}, EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1200..1260=> EnemySpawnTable {
                 global_rate: 1250,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(10, 3),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1260..1320=> EnemySpawnTable  {
                 global_rate: 1500,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(10, 3),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1320..1380=> EnemySpawnTable  {
                 global_rate: 1750,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(10, 3),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1380..1440=> EnemySpawnTable  {
                 global_rate: 2000,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(10, 3),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1440..1600=> EnemySpawnTable  {
                 global_rate: 2250,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(10, 3),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
             1600..i32::MAX=> EnemySpawnTable  {
                 global_rate: 2500,
-                enemy_rates: vec![EnemyRate { enemy_type: EnemyType::Hydra, weight: 3}, EnemyRate { enemy_type: EnemyType::Wyvern, weight: 2}, EnemyRate { enemy_type: EnemyType::Kraken, weight: 1}],
+                elites: EliteRolls::all(10, 3),
+                enemy_rates: vec![EnemyRate { enemy_type: enemy_defs.hydra.clone(), weight: 3}, EnemyRate { enemy_type: enemy_defs.wyvern.clone(), weight: 2}, EnemyRate { enemy_type: enemy_defs.kraken.clone(), weight: 1}],
+                formations: vec![FormationRate::new(Formation::Ring, enemy_defs.elite_serpent.clone(), 24, 2, 60), FormationRate::new(Formation::Pack, enemy_defs.elite_serpent.clone(), 11, 3, 40), FormationRate::new(Formation::Wall, enemy_defs.hydra.clone(), 12, 2, 75), FormationRate::new(Formation::Ambush, enemy_defs.wyvern.clone(), 6, 1, 50)],
             },
         })
     }
 }
 
+// Applied on top of the spawn table for the current time while the player is in a biome
+struct BiomeSpawnOverride {
+    rate_multiplier: f32,
+    // Replaces the weight of these enemies where the spawn table has them
+    enemy_rates: Vec<EnemyRate>,
+}
+
+#[derive(Resource)]
+struct BiomeSpawnOverrides(HashMap<Biome, BiomeSpawnOverride>);
+impl FromWorld for BiomeSpawnOverrides {
+    fn from_world(world: &mut World) -> Self {
+        let enemy_defs = world.resource::<EnemyDefs>();
+        BiomeSpawnOverrides(HashMap::from([
+            (
+                Biome::StormSea,
+                BiomeSpawnOverride {
+                    rate_multiplier: 1.25,
+                    enemy_rates: vec![
+                        EnemyRate {
+                            enemy_type: enemy_defs.siren.clone(),
+                            weight: 4,
+                        },
+                        EnemyRate {
+                            enemy_type: enemy_defs.wyvern.clone(),
+                            weight: 4,
+                        },
+                    ],
+                },
+            ),
+            (
+                Biome::CoralReef,
+                BiomeSpawnOverride {
+                    rate_multiplier: 1.,
+                    enemy_rates: vec![
+                        EnemyRate {
+                            enemy_type: enemy_defs.serpent.clone(),
+                            weight: 6,
+                        },
+                        EnemyRate {
+                            enemy_type: enemy_defs.elite_serpent.clone(),
+                            weight: 6,
+                        },
+                    ],
+                },
+            ),
+            (
+                Biome::IceFloes,
+                BiomeSpawnOverride {
+                    rate_multiplier: 0.8,
+                    enemy_rates: vec![EnemyRate {
+                        enemy_type: enemy_defs.hydra.clone(),
+                        weight: 5,
+                    }],
+                },
+            ),
+            (
+                Biome::Styx,
+                BiomeSpawnOverride {
+                    rate_multiplier: 1.5,
+                    enemy_rates: vec![
+                        EnemyRate {
+                            enemy_type: enemy_defs.kraken.clone(),
+                            weight: 3,
+                        },
+                        EnemyRate {
+                            enemy_type: enemy_defs.siren.clone(),
+                            weight: 1,
+                        },
+                    ],
+                },
+            ),
+        ]))
+    }
+}
+
 fn handle_game_cleanup(mut last_spawn: ResMut<LastSpawn>) {
     last_spawn.0 = 0.;
 }
@@ -225,133 +407,121 @@ fn spawn_enemies(
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     ingame_time: Res<IngameTime>,
     player_query: Query<&Transform, With<Player>>,
-    enemies_query: Query<Entity, With<Enemy>>,
+    enemy_count: Res<EnemyCount>,
     mut last_spawn: ResMut<LastSpawn>,
     enemy_spawn_tables: Res<EnemySpawnTables>,
+    enemy_defs: Res<Assets<EnemyDef>>,
+    boss_fight: Res<BossFight>,
+    director: Res<Director>,
+    current_biome: Res<CurrentBiome>,
+    biome_spawn_overrides: Res<BiomeSpawnOverrides>,
+    weather: Res<Weather>,
 ) {
+    if boss_fight.in_progress() {
+        last_spawn.0 = ingame_time.0;
+        return;
+    }
+
     let player_transform = player_query.get_single().unwrap();
 
     let enemy_spawn_table = enemy_spawn_tables.0.get(&(last_spawn.0 as i32)).unwrap();
+    let biome_override = biome_spawn_overrides.0.get(&current_biome.0);
     let enemy_weights = WeightedIndex::new(
         &enemy_spawn_table
             .enemy_rates
             .iter()
-            .map(|enemy| enemy.weight)
+            .map(|enemy| {
+                biome_override
+                    .and_then(|biome_override| {
+                        biome_override
+                            .enemy_rates
+                            .iter()
+                            .find(|rate| rate.enemy_type == enemy.enemy_type)
+                    })
+                    .map_or(enemy.weight, |rate| rate.weight)
+            })
             .collect::<Vec<i32>>(),
     )
     .unwrap();
+    let rate_multiplier = biome_override
+        .map_or(1., |biome_override| biome_override.rate_multiplier)
+        * weather.spawn_multiplier();
+
+    // The count only catches up next frame
+    let mut spawned = 0;
 
     while last_spawn.0 < ingame_time.0 {
-        last_spawn.0 += 1. / enemy_spawn_table.global_rate as f32 * 100.;
+        last_spawn.0 += 1.
+            / (enemy_spawn_table.global_rate as f32
+                * director.spawn_multiplier()
+                * rate_multiplier)
+            * 100.;
 
-        let entities = enemies_query.iter().count();
-        if entities >= ENTITY_LIMIT {
+        if enemy_count.0 + spawned >= ENTITY_LIMIT {
             continue;
         };
 
         let mut rng = rand::thread_rng();
 
         let enemy_type = &enemy_spawn_table.enemy_rates[enemy_weights.sample(&mut rng)].enemy_type;
+        let Some(enemy_def) = enemy_defs.get(enemy_type) else {
+            continue;
+        };
 
         let random_angle = rng.gen_range((0.)..(2. * PI));
         let spawn_position =
             Vec2::from_angle(random_angle) * SPAWN_DISTANCE + player_transform.translation.xy();
 
-        match enemy_type {
-            EnemyType::Ferris => spawning::spawn_ferris(
-                &mut commands,
-                &asset_server,
-                &mut texture_atlas_layouts,
-                spawn_position,
-            ),
-            EnemyType::Serpent => {
-                spawning::spawn_serpent(&mut commands, &asset_server, spawn_position)
-            }
-            EnemyType::Siren => spawning::spawn_siren(&mut commands, &asset_server, spawn_position),
-            EnemyType::EliteSerpent => {
-                spawning::spawn_elite_serpent(&mut commands, &asset_server, spawn_position)
-            }
-            EnemyType::Hydra => spawning::spawn_hydra(&mut commands, &asset_server, spawn_position),
-            EnemyType::Wyvern => {
-                spawning::spawn_wyvern(&mut commands, &asset_server, spawn_position)
-            }
-            EnemyType::Kraken => {
-                spawning::spawn_kraken(&mut commands, &asset_server, spawn_position)
-            }
+        let enemy = spawning::spawn_enemy(
+            &mut commands,
+            &asset_server,
+            &mut texture_atlas_layouts,
+            enemy_type,
+            enemy_def,
+            spawn_position,
+        );
+        spawned += 1;
+
+        let affixes = enemy_spawn_table
+            .elites
+            .roll(&mut rng, director.elite_multiplier());
+        if !affixes.is_empty() {
+            commands.entity(enemy).insert(Elite { affixes });
         }
     }
 }
 
 #[derive(Component)]
-struct Blahaj;
-fn spawn_blahaj(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    time: Res<Time>,
-    player_query: Query<&Transform, With<Player>>,
-    blahaj_query: Query<&Blahaj>,
-) {
-    let player_transform = player_query.get_single().unwrap();
-    if blahaj_query.get_single().is_ok() {
-        return;
-    }
-
-    let mut rng = rand::thread_rng();
-
-    if rng.gen_bool((BLAHAJ_SPAWN_CHANCE * time.delta_seconds()).into()) {
-        let random_angle = rng.gen_range((0.)..(2. * PI));
-        let spawn_position =
-            Vec2::from_angle(random_angle) * SPAWN_DISTANCE + player_transform.translation.xy();
-
-        commands.spawn((
-            Blahaj,
-            Collider::capsule_x(16.0, 8.0),
-            Sensor,
-            ActiveEvents::COLLISION_EVENTS,
-            ContactWeapon {
-                pierce: -1,
-                damage: 20.,
-            },
-            ActiveCollisionTypes::STATIC_STATIC,
-            SpriteBundle {
-                transform: Transform::from_translation(spawn_position.extend(0.)),
-                texture: asset_server.load("sprites/other/blahaj.png"),
-                ..default()
-            },
-            GameDespawn,
-            YSort(0.),
-            Movement {
-                velocity: Vec2::ZERO,
-                friction: 1.,
-                max_speed: 200.,
-            },
-            HomingBehavior {
-                acceleration: 500.,
-                collided: HashSet::new(),
-            },
-            HomingFlip,
-        ));
-    }
+struct XpOrb {
+    value: f32,
+    dropped_at: f32,
 }
 
+// Pulled in from any distance
 #[derive(Component)]
-struct XpOrb(f32);
+struct Magnetized;
+
+// Stripped from orbs returned to their pool
+type OrbParts = (XpOrb, Movement, Magnetized, Drift);
 
 const SMALL_ORB: f32 = 1.;
 const BIG_ORB: f32 = 10.;
+const ORB_DRIFT: f32 = 0.3;
 
 fn damage_enemies(
     mut commands: Commands,
     time: Res<Time>,
     asset_server: Res<AssetServer>,
     mut ev_damage: EventReader<DamageEvent>,
+    mut ev_enemy_killed: EventWriter<EnemyKilled>,
+    mut ev_enemy_hit: EventWriter<EnemyHit>,
     mut enemy_query: Query<
         (
             Entity,
             &mut Health,
-            &EnemyXp,
             &Transform,
             Option<&mut Movement>,
+            Option<&mut Shield>,
         ),
         With<Enemy>,
     >,
@@ -360,13 +530,28 @@ fn damage_enemies(
     mut sfx_queue: ResMut<SFXQueue>,
 ) {
     for event in ev_damage.read() {
-        let Ok((enemy_entity, mut enemy_health, enemy_xp, enemy_transform, enemy_movement)) =
+        let Ok((enemy_entity, mut enemy_health, enemy_transform, enemy_movement, enemy_shield)) =
             enemy_query.get_mut(event.entity)
         else {
             continue;
         };
 
+        // Already killed by an earlier hit this frame
+        if enemy_health.health < 0. {
+            continue;
+        }
+
+        if let Some(mut enemy_shield) = enemy_shield {
+            if enemy_shield.hits > 0 {
+                enemy_shield.hits -= 1;
+                continue;
+            }
+        }
+
         enemy_health.health -= event.damage;
+        ev_enemy_hit.send(EnemyHit {
+            entity: enemy_entity,
+        });
 
         let asset_handle = asset_server.load("audio/sfx/hit.wav");
         play_sfx(
@@ -384,74 +569,20 @@ fn damage_enemies(
         if enemy_health.health < 0. {
             game_stats.enemies_killed += 1;
 
-            let tween = Tween::new(
-                EaseMethod::Linear,
-                Duration::from_secs_f32(0.5),
-                SpriteColorLens {
-                    start: Color::WHITE,
-                    end: Color::rgba(0., 0., 0., 0.),
-                },
-            )
-            .with_completed_event(0);
-
             commands
                 .entity(enemy_entity)
                 .remove::<Enemy>()
                 .remove::<Health>()
                 .remove::<Movement>()
                 .remove::<ContactEnemy>()
-                .remove::<SurroundAI>()
-                .remove::<ChaseAI>()
-                .remove::<KrakenAI>()
-                .remove::<RangedAI>()
-                .insert((Animator::new(tween), TweenDespawn));
-
-            let big_xp = (enemy_xp.0 / BIG_ORB) as i32;
-            let small_xp = ((enemy_xp.0 / SMALL_ORB) as i32 - big_xp * (BIG_ORB as i32));
-            for i in 0..big_xp as i32 {
-                let mut rng = thread_rng();
-                let direction = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
-
-                commands.spawn((
-                    XpOrb(BIG_ORB),
-                    SpriteBundle {
-                        texture: asset_server.load("sprites/effects/big_xp.png"),
-                        transform: Transform::from_translation(enemy_transform.translation),
-                        ..default()
-                    },
-                    Movement {
-                        max_speed: 1000.,
-                        velocity: direction * rng.gen_range(25.0..75.0),
-                        friction: 0.8,
-                    },
-                    YSort(0.),
-                    DistanceDespawn,
-                    TimedDespawn { delay: 30. },
-                    GameDespawn,
-                ));
-            }
-            for i in 0..small_xp as i32 {
-                let mut rng = thread_rng();
-                let direction = Vec2::from_angle(rng.gen_range(0.0..2.0 * PI));
-
-                commands.spawn((
-                    XpOrb(SMALL_ORB),
-                    SpriteBundle {
-                        texture: asset_server.load("sprites/effects/small_xp.png"),
-                        transform: Transform::from_translation(enemy_transform.translation),
-                        ..default()
-                    },
-                    Movement {
-                        max_speed: 1000.,
-                        velocity: direction * rng.gen_range(25.0..75.0),
-                        friction: 0.8,
-                    },
-                    YSort(0.),
-                    DistanceDespawn,
-                    TimedDespawn { delay: 30. },
-                    GameDespawn,
-                ));
-            }
+                .remove::<EnemyAI>()
+                .remove::<Emitter>();
+
+            ev_enemy_killed.send(EnemyKilled {
+                entity: enemy_entity,
+                position: enemy_transform.translation.xy(),
+                kind: event.kind,
+            });
         }
     }
 }
@@ -461,7 +592,14 @@ const XP_COLLECT_RANGE: f32 = 50.;
 fn update_xp_orbs(
     mut commands: Commands,
     time: Res<Time>,
-    mut xp_orb_query: Query<(&mut Movement, &Transform, &XpOrb, Entity)>,
+    mut xp_orb_query: Query<(
+        &mut Movement,
+        &Transform,
+        &XpOrb,
+        Option<&Magnetized>,
+        Option<&OverflowGem>,
+        Entity,
+    )>,
     player_query: Query<&Transform, With<Player>>,
     mut ev_xp_gain: EventWriter<XpGained>,
     sfx_channel: Res<AudioChannel<SFXChannel>>,
@@ -469,10 +607,12 @@ fn update_xp_orbs(
     mut sfx_queue: ResMut<SFXQueue>,
 ) {
     let player_transform = player_query.get_single().unwrap();
-    for (mut xp_orb_movement, xp_orb_transform, xp_orb, xp_orb_entity) in xp_orb_query.iter_mut() {
+    for (mut xp_orb_movement, xp_orb_transform, xp_orb, magnetized, overflow_gem, xp_orb_entity) in
+        xp_orb_query.iter_mut()
+    {
         let offset = player_transform.translation.xy() - xp_orb_transform.translation.xy();
         let distance = offset.length();
-        if distance > XP_ATTRACT_RANGE {
+        if distance > XP_ATTRACT_RANGE && magnetized.is_none() {
             continue;
         };
 
@@ -488,8 +628,12 @@ fn update_xp_orbs(
                 &sfx_channel,
                 &mut sfx_queue,
             );
-            ev_xp_gain.send(XpGained(xp_orb.0));
-            commands.entity(xp_orb_entity).despawn_recursive();
+            ev_xp_gain.send(XpGained(xp_orb.value));
+            if overflow_gem.is_some() {
+                commands.entity(xp_orb_entity).despawn_recursive();
+            } else {
+                release::<OrbParts>(&mut commands, xp_orb_entity);
+            }
         }
     }
 }
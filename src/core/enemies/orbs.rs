@@ -0,0 +1,210 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::core::{
+    player::Player, GameDespawn, GameState, IngameTime, Movement, PauseState, YSort,
+    DESPAWN_DISTANCE,
+};
+
+use super::{pools::release, update_xp_orbs, OrbParts, XpOrb, BIG_ORB, XP_COLLECT_RANGE};
+
+pub struct OrbsPlugin;
+
+impl Plugin for OrbsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            // Runs after collection so both see the same orb positions and none is counted twice
+            consolidate_xp_orbs
+                .after(update_xp_orbs)
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+const HUGE_ORB: f32 = 100.;
+// Orbs older than this fold into the overflow gem instead of vanishing
+const ORB_LIFETIME: f32 = 30.;
+const ORB_LIMIT: usize = 500;
+
+// Seconds between merge passes
+const MERGE_INTERVAL: f32 = 0.5;
+const MERGE_CELL_SIZE: f32 = 64.;
+// Orbs in one cell before they merge
+const MERGE_DENSITY: usize = 6;
+
+// The overflow gem is kept within this range of the player so it can always be reached
+const OVERFLOW_RANGE: f32 = 400.;
+const OVERFLOW_SCALE: f32 = 2.;
+const OVERFLOW_COLOR: Color = Color::rgb(1., 0.6, 1.);
+
+// Holds the value of every orb that was consolidated away, never expires
+#[derive(Component)]
+pub struct OverflowGem;
+
+pub fn orb_texture(value: f32) -> &'static str {
+    if value >= BIG_ORB {
+        "sprites/effects/big_xp.png"
+    } else {
+        "sprites/effects/small_xp.png"
+    }
+}
+
+fn orb_scale(value: f32) -> f32 {
+    if value >= HUGE_ORB {
+        1.5
+    } else {
+        1.
+    }
+}
+
+fn consolidate_xp_orbs(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    ingame_time: Res<IngameTime>,
+    mut merge_timer: Local<f32>,
+    player_query: Query<&Transform, With<Player>>,
+    mut orb_query: Query<
+        (Entity, &mut XpOrb, &mut Transform, &mut Handle<Image>),
+        (Without<OverflowGem>, Without<Player>),
+    >,
+    mut gem_query: Query<(&mut XpOrb, &mut Transform), (With<OverflowGem>, Without<Player>)>,
+) {
+    let player_position = player_query.get_single().unwrap().translation.xy();
+
+    let mut overflow = 0.;
+    let mut overflow_position = Vec2::ZERO;
+    let mut overflow_count = 0;
+    let mut remaining = Vec::new();
+
+    // Orbs that would have expired or been left behind
+    for (entity, xp_orb, transform, _) in orb_query.iter() {
+        let position = transform.translation.xy();
+        // Already being collected this frame, consolidating it too would count it twice
+        if position.distance(player_position) < XP_COLLECT_RANGE {
+            continue;
+        }
+
+        if ingame_time.0 - xp_orb.dropped_at > ORB_LIFETIME
+            || position.distance(player_position) > DESPAWN_DISTANCE
+        {
+            overflow += xp_orb.value;
+            overflow_position += position;
+            overflow_count += 1;
+            release::<OrbParts>(&mut commands, entity);
+        } else {
+            remaining.push((entity, xp_orb.dropped_at));
+        }
+    }
+
+    // Past the cap the oldest orbs go too
+    if remaining.len() > ORB_LIMIT {
+        remaining.sort_by(|a, b| a.1.total_cmp(&b.1));
+        for (entity, _) in remaining.drain(..remaining.len() - ORB_LIMIT) {
+            let Ok((_, xp_orb, transform, _)) = orb_query.get(entity) else {
+                continue;
+            };
+            overflow += xp_orb.value;
+            overflow_position += transform.translation.xy();
+            overflow_count += 1;
+            release::<OrbParts>(&mut commands, entity);
+        }
+    }
+
+    *merge_timer -= time.delta_seconds();
+    if *merge_timer <= 0. {
+        *merge_timer = MERGE_INTERVAL;
+
+        let mut cells: HashMap<IVec2, Vec<Entity>> = HashMap::new();
+        for (entity, _) in remaining.iter() {
+            let Ok((_, _, transform, _)) = orb_query.get(*entity) else {
+                continue;
+            };
+            let cell = (transform.translation.xy() / MERGE_CELL_SIZE)
+                .floor()
+                .as_ivec2();
+            cells.entry(cell).or_default().push(*entity);
+        }
+
+        for orbs in cells.values() {
+            if orbs.len() < MERGE_DENSITY {
+                continue;
+            }
+
+            let mut value = 0.;
+            let mut dropped_at = f32::NEG_INFINITY;
+            for entity in orbs[1..].iter() {
+                let Ok((_, xp_orb, _, _)) = orb_query.get(*entity) else {
+                    continue;
+                };
+                value += xp_orb.value;
+                dropped_at = dropped_at.max(xp_orb.dropped_at);
+                release::<OrbParts>(&mut commands, *entity);
+            }
+
+            let Ok((_, mut xp_orb, mut transform, mut texture)) = orb_query.get_mut(orbs[0]) else {
+                continue;
+            };
+            xp_orb.value += value;
+            // Merging shouldn't bring an orb closer to expiring
+            xp_orb.dropped_at = xp_orb.dropped_at.max(dropped_at);
+            *texture = asset_server.load(orb_texture(xp_orb.value));
+            transform.scale = Vec3::splat(orb_scale(xp_orb.value));
+        }
+    }
+
+    // Pull the gem back when the player sails away from it
+    for (_, mut transform) in gem_query.iter_mut() {
+        let offset = transform.translation.xy() - player_position;
+        if offset.length() > DESPAWN_DISTANCE {
+            let position = player_position + offset.clamp_length_max(OVERFLOW_RANGE);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+
+    if overflow_count == 0 {
+        return;
+    }
+
+    // A gem being collected this frame can't take any more
+    if let Some((mut xp_orb, _)) = gem_query.iter_mut().find(|(_, transform)| {
+        transform.translation.xy().distance(player_position) >= XP_COLLECT_RANGE
+    }) {
+        xp_orb.value += overflow;
+        return;
+    }
+
+    let centroid = overflow_position / overflow_count as f32;
+    let position = player_position + (centroid - player_position).clamp_length_max(OVERFLOW_RANGE);
+
+    // Not pooled, so it is despawned rather than released when collected
+    commands.spawn((
+        OverflowGem,
+        XpOrb {
+            value: overflow,
+            dropped_at: ingame_time.0,
+        },
+        SpriteBundle {
+            texture: asset_server.load(orb_texture(BIG_ORB)),
+            sprite: Sprite {
+                color: OVERFLOW_COLOR,
+                ..default()
+            },
+            transform: Transform {
+                translation: position.extend(0.),
+                scale: Vec3::splat(OVERFLOW_SCALE),
+                ..default()
+            },
+            ..default()
+        },
+        Movement {
+            max_speed: 1000.,
+            velocity: Vec2::ZERO,
+            friction: 0.8,
+        },
+        YSort(0.),
+        GameDespawn,
+    ));
+}
@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+use crate::core::{player::Player, GameDespawn, GameState, PauseState, DESPAWN_DISTANCE};
+
+// Keeps short lived entities around hidden instead of despawning them,
+// `B` is the set of components stripped from an entity when it is released
+pub struct PoolPlugin<B: Bundle>(PhantomData<B>);
+
+impl<B: Bundle> Default for PoolPlugin<B> {
+    fn default() -> Self {
+        PoolPlugin(PhantomData)
+    }
+}
+
+impl<B: Bundle> Plugin for PoolPlugin<B> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EntityPool::<B>::default())
+            .add_systems(
+                Update,
+                expire_pooled::<B>
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            // Not gated so no release is missed
+            .add_systems(Update, collect_released::<B>)
+            .add_systems(OnEnter(GameState::Game), clear_pool::<B>);
+    }
+}
+
+#[derive(Resource)]
+pub struct EntityPool<B: Bundle> {
+    free: Vec<Entity>,
+    parts: PhantomData<B>,
+}
+
+impl<B: Bundle> Default for EntityPool<B> {
+    fn default() -> Self {
+        EntityPool {
+            free: Vec::new(),
+            parts: PhantomData,
+        }
+    }
+}
+
+impl<B: Bundle> EntityPool<B> {
+    // Reuses a released entity if there is one, the caller inserts the rest of its components.
+    // Without a lifetime the caller is left to release it
+    pub fn acquire(&mut self, commands: &mut Commands, lifetime: Option<f32>) -> Entity {
+        let entity = self
+            .free
+            .pop()
+            .unwrap_or_else(|| commands.spawn(GameDespawn).id());
+
+        commands.entity(entity).insert(Pooled::<B> {
+            lifetime,
+            parts: PhantomData,
+        });
+        entity
+    }
+}
+
+// Released at the end of its lifetime or when left behind, like `TimedDespawn` and `DistanceDespawn`
+#[derive(Component)]
+pub struct Pooled<B: Bundle> {
+    lifetime: Option<f32>,
+    parts: PhantomData<B>,
+}
+
+// Safe to call more than once, the entity only returns to the pool once `Pooled` is gone
+pub fn release<B: Bundle>(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .remove::<(B, Pooled<B>)>()
+        .insert(Visibility::Hidden);
+}
+
+fn expire_pooled<B: Bundle>(
+    mut commands: Commands,
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    mut pooled_query: Query<(Entity, &Transform, &mut Pooled<B>)>,
+) {
+    let player_transform = player_query.get_single().unwrap();
+
+    for (entity, transform, mut pooled) in pooled_query.iter_mut() {
+        let Some(lifetime) = &mut pooled.lifetime else {
+            continue;
+        };
+        *lifetime -= time.delta_seconds();
+
+        let distance = transform
+            .translation
+            .xy()
+            .distance(player_transform.translation.xy());
+        if *lifetime < 0. || distance > DESPAWN_DISTANCE {
+            release::<B>(&mut commands, entity);
+        }
+    }
+}
+
+fn collect_released<B: Bundle>(
+    mut entity_pool: ResMut<EntityPool<B>>,
+    mut ev_released: RemovedComponents<Pooled<B>>,
+    released_query: Query<(), Without<Pooled<B>>>,
+) {
+    // Despawned entities show up here too, they no longer match the query
+    for entity in ev_released.read() {
+        if released_query.contains(entity) {
+            entity_pool.free.push(entity);
+        }
+    }
+}
+
+// Pooled entities are despawned with everything else when a new game starts
+fn clear_pool<B: Bundle>(mut entity_pool: ResMut<EntityPool<B>>) {
+    entity_pool.free.clear();
+}
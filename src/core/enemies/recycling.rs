@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::PI;
+
+use crate::core::{player::Player, GameState, Movement, PauseState, DESPAWN_DISTANCE};
+
+use super::{Enemy, SPAWN_DISTANCE};
+
+pub struct RecyclingPlugin;
+
+impl Plugin for RecyclingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EnemyCount(0))
+            .add_systems(
+                Update,
+                recycle_enemies
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            // Not gated so despawns during cleanup are counted too
+            .add_systems(Update, count_enemies);
+    }
+}
+
+// How far to either side of the player's heading recycled enemies are placed
+const RECYCLE_SPREAD: f32 = PI / 3.;
+
+// Live enemies, kept up to date instead of counting a query every time
+#[derive(Resource)]
+pub struct EnemyCount(pub usize);
+
+// Moved back in front of the player when left behind instead of being despawned
+#[derive(Component)]
+pub struct Recyclable;
+
+fn count_enemies(
+    mut enemy_count: ResMut<EnemyCount>,
+    added_query: Query<(), Added<Enemy>>,
+    mut ev_removed: RemovedComponents<Enemy>,
+) {
+    enemy_count.0 += added_query.iter().count();
+    enemy_count.0 = enemy_count.0.saturating_sub(ev_removed.read().count());
+}
+
+fn recycle_enemies(
+    player_query: Query<(&Transform, &Movement), With<Player>>,
+    mut enemies_query: Query<
+        (&mut Transform, &mut Movement),
+        (With<Enemy>, With<Recyclable>, Without<Player>),
+    >,
+) {
+    let (player_transform, player_movement) = player_query.get_single().unwrap();
+    let player_position = player_transform.translation.xy();
+
+    let mut rng = thread_rng();
+
+    for (mut transform, mut movement) in enemies_query.iter_mut() {
+        let offset = transform.translation.xy() - player_position;
+        if offset.length() <= DESPAWN_DISTANCE {
+            continue;
+        }
+
+        // Ahead of the player, or opposite where the enemy fell behind when standing still
+        let heading = if player_movement.velocity.length_squared() > 1. {
+            player_movement.velocity.to_angle()
+        } else {
+            (-offset).to_angle()
+        };
+        let angle = heading + rng.gen_range(-RECYCLE_SPREAD..RECYCLE_SPREAD);
+        let position = player_position + Vec2::from_angle(angle) * SPAWN_DISTANCE;
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        movement.velocity = Vec2::ZERO;
+    }
+}
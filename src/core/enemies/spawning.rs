@@ -1,301 +1,154 @@
-use crate::core::{DistanceDespawn, GameDespawn, Movement, YSort};
+use crate::core::{
+    obstacles::{sensor_groups, SolidBody},
+    wind::Drift,
+    GameDespawn, Movement, YSort,
+};
 
 use super::{
-    ai::{ChaseAI, EnemyAmmo, KrakenAI, RangedAI, SurroundAI},
-    ContactEnemy, Enemy, EnemyKnockback, EnemyXp, Health, Targetable,
+    ai::{EnemyAI, HitAnimation},
+    animation::{
+        EnemyAnimation, DEATH_ANIMATION, HIT_ANIMATION, HIT_FLASH_DURATION, IDLE_ANIMATION,
+        MOVING_ANIMATION,
+    },
+    defs::{AttackDef, EnemyDef},
+    emitters::Emitter,
+    flocking::Flocking,
+    recycling::Recyclable,
+    ContactEnemy, Enemy, EnemyArchetype, EnemyKnockback, EnemyXp, Health, Targetable,
 };
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use bevy_animations_manager::{AnimationData, AnimationsManager};
 use bevy_rapier2d::prelude::*;
-use rand::{thread_rng, Rng};
 
-pub fn spawn_ferris(
+// Heavier than the ship, the wind pushes them less
+const ENEMY_DRIFT: f32 = 0.5;
+
+pub fn spawn_enemy(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     texture_atlas_layouts: &mut ResMut<Assets<TextureAtlasLayout>>,
+    enemy_handle: &Handle<EnemyDef>,
+    enemy_def: &EnemyDef,
     spawn_position: Vec2,
-) {
-    let texture = asset_server.load("sprites/enemies/ferris.png");
-
-    let layout = TextureAtlasLayout::from_grid(Vec2::new(64.0, 64.0), 1, 1, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-
-    let mut animations_manager = AnimationsManager::new();
+) -> Entity {
+    let texture: Handle<Image> = asset_server.load(&enemy_def.texture);
+    let transform = Transform {
+        translation: spawn_position.extend(0.),
+        scale: Vec3::splat(enemy_def.scale),
+        ..default()
+    };
 
-    animations_manager.load_animation(
-        "alive",
-        AnimationData {
-            texture: texture.clone(),
-            layout: texture_atlas_layout,
-            frame_count: 1,
-            frame_durations: vec![0],
-            anchor: bevy::sprite::Anchor::Center,
-        },
-    );
-
-    animations_manager.play("alive");
-
-    commands.spawn((
+    let mut enemy = commands.spawn((
         (
-            Collider::capsule_x(16.0, 14.0),
+            enemy_def.collider.collider(),
             Sensor,
+            sensor_groups(),
             ActiveEvents::COLLISION_EVENTS,
         ),
-        // ActiveCollisionTypes::STATIC_STATIC,
         Enemy,
+        EnemyArchetype(enemy_handle.clone()),
         Health {
-            health: 25.,
-            max_health: 25.,
+            health: enemy_def.health,
+            max_health: enemy_def.health,
         },
-        ChaseAI { acceleration: 75. },
         Movement {
             velocity: Vec2::ZERO,
             friction: 1.,
-            max_speed: 125.,
-        },
-        SpriteSheetBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture,
-            ..default()
+            max_speed: enemy_def.speed,
         },
         YSort(0.),
-        ContactEnemy,
-        EnemyKnockback { knockback: 320. },
-        animations_manager,
-        Targetable,
-        EnemyXp(1.),
-        DistanceDespawn,
-        GameDespawn,
-    ));
-}
-
-pub fn spawn_serpent(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    spawn_position: Vec2,
-) {
-    commands.spawn((
-        (
-            Collider::capsule_y(16.0, 14.0),
-            Sensor,
-            ActiveEvents::COLLISION_EVENTS,
-        ),
-        Enemy,
-        Health {
-            health: 40.,
-            max_health: 40.,
+        EnemyKnockback {
+            knockback: enemy_def.knockback,
         },
-        ChaseAI { acceleration: 75. },
-        Movement {
-            velocity: Vec2::ZERO,
-            friction: 1.,
-            max_speed: 200.,
-        },
-        SpriteBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture: asset_server.load("sprites/enemies/serpent.png"),
-            ..default()
-        },
-        YSort(0.),
-        ContactEnemy,
-        EnemyKnockback { knockback: 320. },
         Targetable,
-        EnemyXp(5.),
-        DistanceDespawn,
-        GameDespawn,
-    ));
-}
-
-pub fn spawn_siren(commands: &mut Commands, asset_server: &Res<AssetServer>, spawn_position: Vec2) {
-    let mut rng = thread_rng();
-    commands.spawn((
-        (
-            Collider::capsule_x(16.0, 14.0),
-            Sensor,
-            ActiveEvents::COLLISION_EVENTS,
-        ),
-        Enemy,
-        Health {
-            health: 15.,
-            max_health: 15.,
-        },
-        SurroundAI {
-            chase_speed: 150.,
-            surround_speed: 2.5,
-            surround_distance: 250.,
-            clockwise: rng.gen_bool(0.5),
-        },
-        Movement {
-            velocity: Vec2::ZERO,
-            friction: 1.,
-            max_speed: 125.,
-        },
-        SpriteBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture: asset_server.load("sprites/enemies/siren.png"),
-            ..default()
-        },
-        YSort(0.),
-        EnemyKnockback { knockback: 320. },
-        Targetable,
-        EnemyXp(10.),
-        DistanceDespawn,
-        RangedAI {
-            timer: Timer::from_seconds(1.5, TimerMode::Repeating),
-            ammo: EnemyAmmo::Siren,
+        EnemyXp(enemy_def.xp),
+        Recyclable,
+        SolidBody {
+            radius: enemy_def.collider.radius() * enemy_def.scale,
         },
+        Drift(ENEMY_DRIFT),
         GameDespawn,
     ));
-}
 
-pub fn spawn_elite_serpent(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    spawn_position: Vec2,
-) {
-    commands.spawn((
-        (
-            Collider::capsule_y(16.0, 14.0),
-            Sensor,
-            ActiveEvents::COLLISION_EVENTS,
-        ),
-        Enemy,
-        Health {
-            health: 125.,
-            max_health: 125.,
-        },
-        ChaseAI { acceleration: 75. },
-        Movement {
-            velocity: Vec2::ZERO,
-            friction: 1.,
-            max_speed: 200.,
-        },
-        SpriteBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture: asset_server.load("sprites/enemies/elite_serpent.png"),
-            ..default()
-        },
-        YSort(0.),
-        ContactEnemy,
-        EnemyKnockback { knockback: 320. },
-        Targetable,
-        EnemyXp(20.),
-        DistanceDespawn,
-        GameDespawn,
-    ));
-}
+    if enemy_def.contact {
+        enemy.insert(ContactEnemy);
+    }
 
-pub fn spawn_hydra(commands: &mut Commands, asset_server: &Res<AssetServer>, spawn_position: Vec2) {
-    commands.spawn((
-        (
-            Collider::capsule_y(16.0, 14.0),
-            Sensor,
-            ActiveEvents::COLLISION_EVENTS,
-        ),
-        Enemy,
-        Health {
-            health: 200.,
-            max_health: 200.,
-        },
-        ChaseAI { acceleration: 75. },
-        Movement {
-            velocity: Vec2::ZERO,
-            friction: 1.,
-            max_speed: 200.,
-        },
-        SpriteBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture: asset_server.load("sprites/enemies/hydra.png"),
-            ..default()
-        },
-        YSort(0.),
-        ContactEnemy,
-        EnemyKnockback { knockback: 500. },
-        Targetable,
-        EnemyXp(35.),
-        DistanceDespawn,
-        GameDespawn,
-    ));
-}
+    enemy.insert(EnemyAI::new());
 
-pub fn spawn_wyvern(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    spawn_position: Vec2,
-) {
-    let mut rng = thread_rng();
-    commands.spawn((
-        (
-            Collider::capsule_x(16.0, 14.0),
-            Sensor,
-            ActiveEvents::COLLISION_EVENTS,
-        ),
-        Enemy,
-        Health {
-            health: 150.,
-            max_health: 150.,
-        },
-        SurroundAI {
-            chase_speed: 150.,
-            surround_speed: 10.,
-            surround_distance: 300.,
-            clockwise: rng.gen_bool(0.5),
-        },
-        Movement {
-            velocity: Vec2::ZERO,
-            friction: 1.,
-            max_speed: 125.,
-        },
-        SpriteBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture: asset_server.load("sprites/enemies/wyvern.png"),
+    if let Some(attack) = &enemy_def.attack {
+        insert_attack(&mut enemy, attack);
+    }
+
+    if let Some(flocking) = enemy_def.flocking {
+        enemy.insert(Flocking(flocking));
+    }
+
+    let animation = &enemy_def.animation;
+    let mut animations_manager = AnimationsManager::new();
+
+    let mut enemy_animation = EnemyAnimation::new(spawn_position);
+
+    // States without a clip are left out and keep whatever is playing
+    for (name, clip) in [
+        (IDLE_ANIMATION, Some(&animation.idle)),
+        (MOVING_ANIMATION, animation.moving.as_ref()),
+        (HIT_ANIMATION, animation.hit.as_ref()),
+        (DEATH_ANIMATION, animation.death.as_ref()),
+    ] {
+        let Some(clip) = clip else {
+            continue;
+        };
+        enemy_animation.add_clip(name);
+
+        let layout = TextureAtlasLayout::from_grid(
+            Vec2::new(animation.frame_width, animation.frame_height),
+            clip.columns,
+            clip.rows,
+            None,
+            None,
+        );
+
+        animations_manager.load_animation(
+            name,
+            AnimationData {
+                texture: clip
+                    .texture
+                    .as_ref()
+                    .map_or(texture.clone(), |clip_texture| {
+                        asset_server.load(clip_texture)
+                    }),
+                layout: texture_atlas_layouts.add(layout),
+                frame_count: clip.frame_count,
+                frame_durations: clip
+                    .frame_durations
+                    .iter()
+                    .map(|duration| *duration as _)
+                    .collect(),
+                anchor: bevy::sprite::Anchor::Center,
+            },
+        );
+    }
+
+    animations_manager.play(IDLE_ANIMATION);
+
+    enemy.insert((
+        SpriteSheetBundle {
+            transform,
+            texture,
             ..default()
         },
-        YSort(0.),
-        EnemyKnockback { knockback: 320. },
-        Targetable,
-        EnemyXp(50.),
-        DistanceDespawn,
-        RangedAI {
-            timer: Timer::from_seconds(1.5, TimerMode::Repeating),
-            ammo: EnemyAmmo::Wyvern,
+        animations_manager,
+        enemy_animation,
+        HitAnimation {
+            duration: HIT_FLASH_DURATION,
+            last_hit: f32::NEG_INFINITY,
         },
-        GameDespawn,
     ));
+
+    enemy.id()
 }
 
-pub fn spawn_kraken(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    spawn_position: Vec2,
-) {
-    commands.spawn((
-        (Collider::ball(16.0), Sensor, ActiveEvents::COLLISION_EVENTS),
-        Enemy,
-        Health {
-            health: 1000.,
-            max_health: 1000.,
-        },
-        ChaseAI { acceleration: 75. },
-        Movement {
-            velocity: Vec2::ZERO,
-            friction: 1.,
-            max_speed: 150.,
-        },
-        SpriteBundle {
-            transform: Transform::from_translation(spawn_position.extend(0.)),
-            texture: asset_server.load("sprites/enemies/kraken.png"),
-            ..default()
-        },
-        YSort(0.),
-        ContactEnemy,
-        EnemyKnockback { knockback: 320. },
-        Targetable,
-        EnemyXp(100.),
-        DistanceDespawn,
-        KrakenAI {
-            timer: Timer::from_seconds(5., TimerMode::Repeating),
-        },
-        GameDespawn,
-    ));
+pub fn insert_attack(enemy: &mut EntityCommands, attack: &AttackDef) {
+    enemy.insert(Emitter::new(attack));
 }
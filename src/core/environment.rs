@@ -2,17 +2,26 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::{
     distributions::{Distribution, WeightedIndex},
-    thread_rng, Rng,
+    rngs::StdRng,
+    thread_rng, Rng, SeedableRng,
 };
 use rangemap::{range_map, RangeMap};
-use std::{collections::HashSet, f32::consts::PI};
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+};
 
 use crate::core::{GameState, PauseState};
 
 use super::{
+    biomes::{biome_at, hash_cell},
     enemies::{ContactEnemy, Enemy, EnemyKnockback},
+    hazards::{spawn_charybdis, spawn_reef, spawn_whirlpool, HazardTextures},
     items::{get_item_sprite, Inventory, Item, INVENTORY_SIZE},
+    obstacles::{sensor_groups, solid_groups, CollisionMode, Solid},
     player::Player,
+    ports::spawn_harbor,
+    wind::Wind,
     GameDespawn, GameStats, IngameTime, YSort,
 };
 
@@ -20,10 +29,16 @@ pub struct BackgroundPlugin;
 
 impl Plugin for BackgroundPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(CurrentTileChunks(HashSet::new()))
-            .insert_resource(CurrentEntityChunks(HashSet::new()))
+        app.insert_resource(CurrentTileChunks::default())
+            .insert_resource(CurrentEntityChunks::default())
+            .insert_resource(TilePool::default())
+            .insert_resource(RenderDistance::default())
             .insert_resource(ItemSpawnTables::default())
+            .insert_resource(WorldSeed(0))
+            .insert_resource(ChunkMemory::default())
             .add_systems(Update, (update_tile_chunks, update_offset))
+            // Not gated so no removal is missed
+            .add_systems(Update, remember_cleared_entities)
             .add_systems(
                 Update,
                 update_entity_chunks.run_if(in_state(GameState::Game)),
@@ -34,26 +49,102 @@ impl Plugin for BackgroundPlugin {
                     .run_if(in_state(GameState::Game))
                     .run_if(in_state(PauseState::Running)),
             )
-            .add_systems(OnEnter(GameState::Game), cleanup_entity_chunks);
+            .add_systems(
+                Update,
+                cycle_render_distance.run_if(in_state(GameState::StartMenu)),
+            )
+            .add_systems(
+                OnEnter(GameState::Game),
+                (cleanup_entity_chunks, cleanup_tile_chunks),
+            );
     }
 }
 
 const TILE_SIZE: f32 = 64.;
-const CHUNK_SIZE: f32 = TILE_SIZE * 5.;
-const RENDER_DISTANCE_Y: u16 = 4;
-const RENDER_DISTANCE_X: u16 = 4;
+pub const CHUNK_SIZE: f32 = TILE_SIZE * 5.;
+// Chosen from the start menu
+const RENDER_DISTANCES: [u16; 3] = [3, 4, 6];
+
+// Chunks loaded on each side of the player's chunk
+#[derive(Resource)]
+pub struct RenderDistance {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Default for RenderDistance {
+    fn default() -> Self {
+        RenderDistance { x: 4, y: 4 }
+    }
+}
 
-#[derive(Resource, Debug)]
-pub struct CurrentTileChunks(HashSet<IVec2>);
+impl RenderDistance {
+    fn contains(&self, center: IVec2, chunk: IVec2) -> bool {
+        (chunk.x - center.x).abs() <= i32::from(self.x)
+            && (chunk.y - center.y).abs() <= i32::from(self.y)
+    }
+}
 
-#[derive(Resource, Debug)]
-pub struct CurrentEntityChunks(HashSet<IVec2>);
+#[derive(Resource, Debug, Default)]
+pub struct CurrentTileChunks {
+    // Tile entities and their layer
+    chunks: HashMap<IVec2, Vec<(usize, Entity)>>,
+    // The player's chunk when they were last streamed in
+    center: Option<IVec2>,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct CurrentEntityChunks {
+    chunks: HashSet<IVec2>,
+    center: Option<IVec2>,
+}
+
+// Hidden tiles waiting to be reused, one list per layer
+#[derive(Resource, Default)]
+struct TilePool([Vec<Entity>; OVERLAY_LAYER + 1]);
+
+type TileParts = (
+    &'static mut Chunk,
+    &'static mut Transform,
+    &'static mut Sprite,
+    &'static mut Visibility,
+    Option<&'static mut AnimateOffset>,
+);
 
 #[derive(Component)]
 struct Chunk {
     pos: IVec2,
 }
 
+// Rolled at the start of every run, chunk contents are derived from it and the chunk position
+#[derive(Resource)]
+pub struct WorldSeed(pub u64);
+
+// Which of a chunk's entities it is, so a cleared one stays gone when the chunk is revisited
+const ITEM_SLOT: u32 = 0;
+// Rocks take the slots after this one
+const ROCK_SLOT: u32 = 1;
+const MAX_ROCKS: u32 = 3;
+const ISLAND_SLOT: u32 = ROCK_SLOT + MAX_ROCKS;
+const CHARYBDIS_SLOT: u32 = ISLAND_SLOT + 1;
+
+#[derive(Resource, Default)]
+struct ChunkMemory {
+    // Items are picked from the spawn table of the first visit
+    items: HashMap<IVec2, Item>,
+    loaded: HashMap<Entity, (IVec2, u32)>,
+    cleared: HashSet<(IVec2, u32)>,
+}
+
+pub fn chunk_rng(world_seed: &WorldSeed, chunk: IVec2) -> StdRng {
+    StdRng::seed_from_u64(hash_cell(world_seed.0, chunk))
+}
+
+// Chunks are centered on their position, like the tiles drawn for them
+pub fn chunk_at(position: Vec2) -> IVec2 {
+    (position / CHUNK_SIZE).round().as_ivec2()
+}
+
 #[derive(Component)]
 struct AnimateOffset {
     angle: f32,
@@ -72,7 +163,7 @@ struct ItemSpawnTable {
 }
 
 #[derive(Component)]
-struct ItemPickup {
+pub struct ItemPickup {
     item_type: Item,
 }
 
@@ -106,22 +197,22 @@ impl ItemSpawnTables {
 // Per Chunk
 const ITEM_RATE: f32 = 0.75;
 
+// Only with solid obstacles on, per rock
+const LARGE_ROCK_RATE: f32 = 0.25;
+const LARGE_ROCK_SCALE: f32 = 2.5;
 // Per Chunk
-const ROCK_RATE: f32 = 0.8;
-
-fn get_chunks_needed(
-    current_tile_chunk: &IVec2,
-    render_distance_x: u16,
-    render_distance_y: u16,
-) -> Vec<IVec2> {
-    let render_distance_x = i32::from(render_distance_x);
-    let render_distance_y = i32::from(render_distance_y);
+const ISLAND_RATE: f32 = 0.06;
+const ISLAND_SCALE: f32 = 5.;
+const ISLAND_COLOR: Color = Color::rgb(0.85, 0.75, 0.5);
+const HARBOR_RATE: f32 = 0.015;
+const CHARYBDIS_RATE: f32 = 0.003;
+
+fn get_chunks_needed(current_chunk: &IVec2, render_distance: &RenderDistance) -> Vec<IVec2> {
+    let render_distance_x = i32::from(render_distance.x);
+    let render_distance_y = i32::from(render_distance.y);
     let mut chunks_needed: Vec<IVec2> = Vec::new();
-    for x in (current_tile_chunk.x - render_distance_x)..=(current_tile_chunk.x + render_distance_x)
-    {
-        for y in
-            (current_tile_chunk.y - render_distance_y)..=(current_tile_chunk.y + render_distance_y)
-        {
+    for x in (current_chunk.x - render_distance_x)..=(current_chunk.x + render_distance_x) {
+        for y in (current_chunk.y - render_distance_y)..=(current_chunk.y + render_distance_y) {
             chunks_needed.push(IVec2::new(x, y));
         }
     }
@@ -132,66 +223,69 @@ fn get_chunks_needed(
 #[derive(Component)]
 struct TileChunk;
 
-fn update_tile_chunks(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut current_tile_chunks: ResMut<CurrentTileChunks>,
-    player_query: Query<&Transform, With<Player>>,
-    tile_chunks_query: Query<(Entity, &Chunk), With<TileChunk>>,
-) {
-    // Create new chunks
-    let Ok(transform) = player_query.get_single() else {
-        return;
-    };
+// Drawn back to front, the overlay only where the biome has one
+const WATER_LAYER: usize = 0;
+const WAVE_LAYER: usize = 1;
+const OVERLAY_LAYER: usize = 2;
 
-    let current_pos = transform.translation;
-    let current_chunk = (current_pos.xy() / CHUNK_SIZE).as_ivec2();
-
-    let chunks_needed = get_chunks_needed(&current_chunk, RENDER_DISTANCE_X, RENDER_DISTANCE_Y);
+fn tile_texture(layer: usize) -> &'static str {
+    match layer {
+        WATER_LAYER => "sprites/tiles/water_bg.png",
+        WAVE_LAYER => "sprites/tiles/water_layer_1.png",
+        _ => "sprites/tiles/water_layer_2.png",
+    }
+}
 
-    for chunk in &chunks_needed {
-        if current_tile_chunks.0.contains(&chunk) {
-            continue;
-        };
+// One layer of a chunk's water, tinted and animated for its biome
+struct TileSpec {
+    layer: usize,
+    chunk: IVec2,
+    color: Color,
+    speed: f32,
+}
 
-        current_tile_chunks.0.insert(*chunk);
+// Reuses a pooled tile if there is one, otherwise spawns it
+fn place_tile(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    tile_pool: &mut TilePool,
+    tiles_query: &mut Query<TileParts, (With<TileChunk>, Without<Player>)>,
+    spec: TileSpec,
+) -> Entity {
+    let TileSpec {
+        layer,
+        chunk,
+        color,
+        speed,
+    } = spec;
+    let chunk_pos = chunk.as_vec2() * CHUNK_SIZE;
+
+    if let Some(entity) = tile_pool.0[layer].pop() {
+        if let Ok((mut tile_chunk, mut transform, mut sprite, mut visibility, animate_offset)) =
+            tiles_query.get_mut(entity)
+        {
+            tile_chunk.pos = chunk;
+            transform.translation.x = chunk_pos.x;
+            transform.translation.y = chunk_pos.y;
+            sprite.color = color;
+            *visibility = Visibility::Inherited;
+            if let Some(mut animate_offset) = animate_offset {
+                animate_offset.speed = speed;
+            }
+            return entity;
+        }
+    }
 
-        let chunk_pos = chunk.as_vec2() * CHUNK_SIZE;
-        let chunk_x_range = (chunk_pos.x)..(chunk_pos.x + CHUNK_SIZE);
-        let chunk_y_range = (chunk_pos.y)..(chunk_pos.y + CHUNK_SIZE);
-
-        // commands.spawn((
-        //     Chunk { pos: *chunk },
-        //     SpriteBundle {
-        //         texture: asset_server.load("sprites/tiles/water_layer_2.png"),
-        //         transform: Transform::from_translation(chunk_pos.extend(0.)),
-        //         sprite: Sprite {
-        //             custom_size: Some(Vec2::splat(CHUNK_SIZE)),
-        //             color: Color::rgba(1., 1., 1., 0.5),
-        //             ..default()
-        //         },
-        //         ..default()
-        //     },
-        //     ImageScaleMode::Tiled {
-        //         tile_x: true,
-        //         tile_y: true,
-        //         stretch_value: 1.0, // The image will tile every 128px
-        //     },
-        //     AnimateOffset {
-        //         angle: 0.25 * PI,
-        //         speed: 3.,
-        //     },
-        //     YSort(-2.),
-        // ));
-
-        commands.spawn((
-            Chunk { pos: *chunk },
+    let tile = commands
+        .spawn((
+            TileChunk,
+            Chunk { pos: chunk },
             SpriteBundle {
-                texture: asset_server.load("sprites/tiles/water_layer_1.png"),
+                texture: asset_server.load(tile_texture(layer)),
                 transform: Transform::from_translation(chunk_pos.extend(0.)),
                 sprite: Sprite {
                     custom_size: Some(Vec2::splat(CHUNK_SIZE)),
-                    color: Color::rgba(1., 1., 1., 0.5),
+                    color,
                     ..default()
                 },
                 ..default()
@@ -201,38 +295,106 @@ fn update_tile_chunks(
                 tile_y: true,
                 stretch_value: 1.0, // The image will tile every 128px
             },
-            AnimateOffset {
+            YSort(-4. + layer as f32),
+        ))
+        .id();
+
+    match layer {
+        WAVE_LAYER => {
+            commands.entity(tile).insert(AnimateOffset {
                 angle: 0.25 * PI,
-                speed: 3.,
-            },
-            YSort(-3.),
-        ));
+                speed,
+            });
+        }
+        OVERLAY_LAYER => {
+            commands.entity(tile).insert(AnimateOffset {
+                angle: 0.75 * PI,
+                speed,
+            });
+        }
+        _ => {}
+    }
 
-        commands.spawn((
-            Chunk { pos: *chunk },
-            SpriteBundle {
-                texture: asset_server.load("sprites/tiles/water_bg.png"),
-                transform: Transform::from_translation(chunk_pos.extend(0.)),
-                sprite: Sprite {
-                    custom_size: Some(Vec2::splat(CHUNK_SIZE)),
-                    ..default()
-                },
-                ..default()
-            },
-            ImageScaleMode::Tiled {
-                tile_x: true,
-                tile_y: true,
-                stretch_value: 1.0, // The image will tile every 128px
-            },
-            YSort(-4.),
-        ));
+    tile
+}
+
+fn release_tile(
+    tile_pool: &mut TilePool,
+    tiles_query: &mut Query<TileParts, (With<TileChunk>, Without<Player>)>,
+    layer: usize,
+    entity: Entity,
+) {
+    if let Ok((_, _, _, mut visibility, _)) = tiles_query.get_mut(entity) {
+        *visibility = Visibility::Hidden;
+        tile_pool.0[layer].push(entity);
     }
+}
 
-    for (entity, chunk) in tile_chunks_query.iter() {
-        if !chunks_needed.contains(&chunk.pos) {
-            current_tile_chunks.0.remove(&chunk.pos);
-            commands.entity(entity).despawn_recursive();
+fn update_tile_chunks(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut current_tile_chunks: ResMut<CurrentTileChunks>,
+    mut tile_pool: ResMut<TilePool>,
+    render_distance: Res<RenderDistance>,
+    world_seed: Res<WorldSeed>,
+    player_query: Query<&Transform, With<Player>>,
+    mut tiles_query: Query<TileParts, (With<TileChunk>, Without<Player>)>,
+) {
+    let Ok(transform) = player_query.get_single() else {
+        return;
+    };
+
+    let current_chunk = chunk_at(transform.translation.xy());
+
+    // Only streamed when the player crosses into another chunk
+    if current_tile_chunks.center == Some(current_chunk) && !render_distance.is_changed() {
+        return;
+    }
+    current_tile_chunks.center = Some(current_chunk);
+
+    current_tile_chunks.chunks.retain(|chunk, tiles| {
+        if render_distance.contains(current_chunk, *chunk) {
+            return true;
         }
+        for (layer, entity) in tiles.iter() {
+            release_tile(&mut tile_pool, &mut tiles_query, *layer, *entity);
+        }
+        false
+    });
+
+    for chunk in get_chunks_needed(&current_chunk, &render_distance) {
+        if current_tile_chunks.chunks.contains_key(&chunk) {
+            continue;
+        };
+
+        let biome = biome_at(&world_seed, chunk).def();
+        let mut layers = vec![
+            (WATER_LAYER, biome.water_color),
+            (WAVE_LAYER, biome.layer_color),
+        ];
+        if let Some(overlay_color) = biome.overlay {
+            layers.push((OVERLAY_LAYER, overlay_color));
+        }
+
+        let tiles = layers
+            .into_iter()
+            .map(|(layer, color)| {
+                let tile = place_tile(
+                    &mut commands,
+                    &asset_server,
+                    &mut tile_pool,
+                    &mut tiles_query,
+                    TileSpec {
+                        layer,
+                        chunk,
+                        color,
+                        speed: biome.layer_speed,
+                    },
+                );
+                (layer, tile)
+            })
+            .collect();
+        current_tile_chunks.chunks.insert(chunk, tiles);
     }
 }
 
@@ -245,121 +407,356 @@ fn update_entity_chunks(
     player_query: Query<&Transform, With<Player>>,
     entity_chunks_query: Query<(Entity, &Chunk), With<EntityChunk>>,
     mut current_entity_chunks: ResMut<CurrentEntityChunks>,
+    mut chunk_memory: ResMut<ChunkMemory>,
+    render_distance: Res<RenderDistance>,
+    world_seed: Res<WorldSeed>,
+    collision_mode: Res<CollisionMode>,
+    hazard_textures: Res<HazardTextures>,
     ingame_time: Res<IngameTime>,
     item_spawn_tables: Res<ItemSpawnTables>,
 ) {
     let item_spawn_table = item_spawn_tables.0.get(&(ingame_time.0 as i32)).unwrap();
-    let item_weights = WeightedIndex::new(
-        &item_spawn_table
-            .item_rates
-            .iter()
-            .map(|item| item.weight)
-            .collect::<Vec<i32>>(),
-    )
-    .unwrap();
 
-    // Create new chunks
     let Ok(transform) = player_query.get_single() else {
         return;
     };
 
     let current_pos = transform.translation;
-    let current_chunk = (current_pos.xy() / CHUNK_SIZE).as_ivec2();
+    let current_chunk = chunk_at(current_pos.xy());
 
-    let chunks_needed = get_chunks_needed(&current_chunk, RENDER_DISTANCE_X, RENDER_DISTANCE_Y);
+    // Only streamed when the player crosses into another chunk
+    if current_entity_chunks.center == Some(current_chunk) && !render_distance.is_changed() {
+        return;
+    }
+    current_entity_chunks.center = Some(current_chunk);
 
-    for chunk in &chunks_needed {
-        if current_entity_chunks.0.contains(&chunk) {
+    for (entity, chunk) in entity_chunks_query.iter() {
+        if !render_distance.contains(current_chunk, chunk.pos) {
+            // Unloaded rather than cleared
+            chunk_memory.loaded.remove(&entity);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    current_entity_chunks
+        .chunks
+        .retain(|chunk| render_distance.contains(current_chunk, *chunk));
+
+    // Create new chunks
+    for chunk in &get_chunks_needed(&current_chunk, &render_distance) {
+        if !current_entity_chunks.chunks.insert(*chunk) {
             continue;
         };
 
-        current_entity_chunks.0.insert(*chunk);
-
         let chunk_pos = chunk.as_vec2() * CHUNK_SIZE;
-        let chunk_x_range = (chunk_pos.x)..(chunk_pos.x + CHUNK_SIZE);
-        let chunk_y_range = (chunk_pos.y)..(chunk_pos.y + CHUNK_SIZE);
-
-        let mut rng = thread_rng();
-        if rng.gen_bool(ITEM_RATE.into()) {
-            let spawn_location = Vec2::new(
-                rng.gen_range(chunk_x_range.clone()),
-                rng.gen_range(chunk_y_range.clone()),
-            );
-            if (spawn_location - current_pos.xy()).length() > 500. {
-                let item = item_spawn_table.item_rates[item_weights.sample(&mut rng)].item_type;
+        let chunk_x_range = (chunk_pos.x - CHUNK_SIZE / 2.)..(chunk_pos.x + CHUNK_SIZE / 2.);
+        let chunk_y_range = (chunk_pos.y - CHUNK_SIZE / 2.)..(chunk_pos.y + CHUNK_SIZE / 2.);
+
+        let biome = biome_at(&world_seed, *chunk).def();
+        let item_weights = WeightedIndex::new(
+            &item_spawn_table
+                .item_rates
+                .iter()
+                .map(|item| {
+                    biome
+                        .item_weights
+                        .iter()
+                        .find(|(item_type, _)| *item_type == item.item_type)
+                        .map_or(item.weight, |(_, weight)| *weight)
+                })
+                .collect::<Vec<i32>>(),
+        )
+        .unwrap();
+
+        // Every roll is made whether or not it is used, so a chunk rolls the same layout on every
+        // visit. Anything rolled too close to the player is skipped and turns up on a later visit
+        let mut rng = chunk_rng(&world_seed, *chunk);
+        let has_item = rng.gen_bool(ITEM_RATE.into());
+        let item_location = Vec2::new(
+            rng.gen_range(chunk_x_range.clone()),
+            rng.gen_range(chunk_y_range.clone()),
+        );
+        let sampled_item = item_spawn_table.item_rates[item_weights.sample(&mut rng)].item_type;
+        let rock_count = (biome.obstacle_rate as u32
+            + rng.gen_bool(biome.obstacle_rate.fract().into()) as u32)
+            .min(MAX_ROCKS);
+        let rocks: Vec<(Vec2, bool)> = (0..MAX_ROCKS)
+            .map(|_| {
+                let location = Vec2::new(
+                    rng.gen_range(chunk_x_range.clone()),
+                    rng.gen_range(chunk_y_range.clone()),
+                );
+                (location, rng.gen_bool(LARGE_ROCK_RATE.into()))
+            })
+            .collect();
+        let has_island = rng.gen_bool(ISLAND_RATE.into());
+        let island_location = Vec2::new(
+            rng.gen_range(chunk_x_range.clone()),
+            rng.gen_range(chunk_y_range.clone()),
+        );
+        let has_harbor = rng.gen_bool(HARBOR_RATE.into());
+        let harbor_location = Vec2::new(
+            rng.gen_range(chunk_x_range.clone()),
+            rng.gen_range(chunk_y_range.clone()),
+        );
+        let has_whirlpool = rng.gen_bool(biome.whirlpool_rate.into());
+        let whirlpool_location = Vec2::new(
+            rng.gen_range(chunk_x_range.clone()),
+            rng.gen_range(chunk_y_range.clone()),
+        );
+        let has_reef = rng.gen_bool(biome.reef_rate.into());
+        let reef_location = Vec2::new(
+            rng.gen_range(chunk_x_range.clone()),
+            rng.gen_range(chunk_y_range.clone()),
+        );
+        let has_charybdis = rng.gen_bool(CHARYBDIS_RATE.into());
+        let charybdis_location = Vec2::new(
+            rng.gen_range(chunk_x_range.clone()),
+            rng.gen_range(chunk_y_range.clone()),
+        );
+
+        if has_item
+            && !chunk_memory.cleared.contains(&(*chunk, ITEM_SLOT))
+            && (item_location - current_pos.xy()).length() > 500.
+        {
+            let item = *chunk_memory.items.entry(*chunk).or_insert(sampled_item);
 
-                commands.spawn((
-                    EntityChunk,
-                    Chunk { pos: *chunk },
-                    ItemPickup { item_type: item },
-                    Collider::ball(32.),
-                    Sensor,
-                    ActiveEvents::COLLISION_EVENTS,
-                    SpriteBundle {
-                        texture: asset_server.load(get_item_sprite(&item)),
-                        transform: Transform::from_translation(spawn_location.extend(0.)),
-                        ..default()
-                    },
-                    YSort(0.),
-                    GameDespawn,
-                ));
-            };
+            let item_entity = spawn_item_pickup(&mut commands, &asset_server, item, item_location);
+            commands
+                .entity(item_entity)
+                .insert((EntityChunk, Chunk { pos: *chunk }));
+            chunk_memory.loaded.insert(item_entity, (*chunk, ITEM_SLOT));
         }
-        if rng.gen_bool(ROCK_RATE.into()) {
-            let spawn_location = Vec2::new(
-                rng.gen_range(chunk_x_range.clone()),
-                rng.gen_range(chunk_y_range.clone()),
-            );
-            if (spawn_location - current_pos.xy()).length() > 500. {
-                commands.spawn((
+        for (i, (rock_location, large)) in rocks.iter().take(rock_count as usize).enumerate() {
+            let slot = ROCK_SLOT + i as u32;
+            if chunk_memory.cleared.contains(&(*chunk, slot))
+                || (*rock_location - current_pos.xy()).length() <= 500.
+            {
+                continue;
+            }
+
+            if *large && collision_mode.solid {
+                let rock_entity = spawn_solid(
+                    &mut commands,
+                    &asset_server,
+                    *rock_location,
+                    LARGE_ROCK_SCALE,
+                    biome.obstacle_color,
+                );
+                commands
+                    .entity(rock_entity)
+                    .insert((EntityChunk, Chunk { pos: *chunk }));
+                chunk_memory.loaded.insert(rock_entity, (*chunk, slot));
+                continue;
+            }
+
+            let rock_entity = commands
+                .spawn((
                     EntityChunk,
                     Chunk { pos: *chunk },
                     EnemyKnockback { knockback: 160. },
                     ContactEnemy,
                     Collider::ball(16.),
                     Sensor,
+                    sensor_groups(),
                     ActiveEvents::COLLISION_EVENTS,
                     SpriteBundle {
                         texture: asset_server.load("sprites/obstacles/rock.png"),
-                        transform: Transform::from_translation(spawn_location.extend(0.)),
+                        transform: Transform::from_translation(rock_location.extend(0.)),
+                        sprite: Sprite {
+                            color: biome.obstacle_color,
+                            ..default()
+                        },
                         ..default()
                     },
                     YSort(0.),
                     GameDespawn,
-                ));
-            };
+                ))
+                .id();
+            chunk_memory.loaded.insert(rock_entity, (*chunk, slot));
+        }
+        if has_island
+            && collision_mode.solid
+            && !chunk_memory.cleared.contains(&(*chunk, ISLAND_SLOT))
+            && (island_location - current_pos.xy()).length() > 500.
+        {
+            let island_entity = spawn_solid(
+                &mut commands,
+                &asset_server,
+                island_location,
+                ISLAND_SCALE,
+                ISLAND_COLOR,
+            );
+            commands
+                .entity(island_entity)
+                .insert((EntityChunk, Chunk { pos: *chunk }));
+            chunk_memory
+                .loaded
+                .insert(island_entity, (*chunk, ISLAND_SLOT));
+        }
+        // Harbors can't be cleared, so they aren't remembered
+        if has_harbor && (harbor_location - current_pos.xy()).length() > 500. {
+            let harbor_entity = spawn_harbor(
+                &mut commands,
+                &asset_server,
+                *chunk,
+                harbor_location,
+                collision_mode.solid,
+            );
+            commands
+                .entity(harbor_entity)
+                .insert((EntityChunk, Chunk { pos: *chunk }));
+        }
+        if has_whirlpool && (whirlpool_location - current_pos.xy()).length() > 500. {
+            let whirlpool_entity =
+                spawn_whirlpool(&mut commands, &hazard_textures, whirlpool_location);
+            commands
+                .entity(whirlpool_entity)
+                .insert((EntityChunk, Chunk { pos: *chunk }));
+        }
+        if has_reef && (reef_location - current_pos.xy()).length() > 500. {
+            let reef_entity = spawn_reef(&mut commands, &hazard_textures, reef_location);
+            commands
+                .entity(reef_entity)
+                .insert((EntityChunk, Chunk { pos: *chunk }));
+        }
+        // Once she has opened she stays gone
+        if has_charybdis
+            && !chunk_memory.cleared.contains(&(*chunk, CHARYBDIS_SLOT))
+            && (charybdis_location - current_pos.xy()).length() > 500.
+        {
+            let charybdis_entity =
+                spawn_charybdis(&mut commands, &hazard_textures, charybdis_location);
+            commands
+                .entity(charybdis_entity)
+                .insert((EntityChunk, Chunk { pos: *chunk }));
+            chunk_memory
+                .loaded
+                .insert(charybdis_entity, (*chunk, CHARYBDIS_SLOT));
         }
     }
+}
 
-    for (entity, chunk) in entity_chunks_query.iter() {
-        if !chunks_needed.contains(&chunk.pos) {
-            current_entity_chunks.0.remove(&chunk.pos);
-            commands.entity(entity).despawn_recursive();
+// Anything still loaded when it goes was picked up or destroyed
+fn remember_cleared_entities(
+    mut chunk_memory: ResMut<ChunkMemory>,
+    mut ev_removed: RemovedComponents<EntityChunk>,
+) {
+    for entity in ev_removed.read() {
+        if let Some(slot) = chunk_memory.loaded.remove(&entity) {
+            chunk_memory.cleared.insert(slot);
         }
     }
 }
 
+// Blocks movement and projectiles but never deals damage
+fn spawn_solid(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    spawn_location: Vec2,
+    scale: f32,
+    color: Color,
+) -> Entity {
+    commands
+        .spawn((
+            Solid,
+            Collider::ball(16.),
+            RigidBody::Fixed,
+            solid_groups(),
+            SpriteBundle {
+                texture: asset_server.load("sprites/obstacles/rock.png"),
+                transform: Transform {
+                    translation: spawn_location.extend(0.),
+                    scale: Vec3::splat(scale),
+                    ..default()
+                },
+                sprite: Sprite { color, ..default() },
+                ..default()
+            },
+            YSort(0.),
+            GameDespawn,
+        ))
+        .id()
+}
+
+pub fn spawn_item_pickup(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    item: Item,
+    spawn_location: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            ItemPickup { item_type: item },
+            Collider::ball(32.),
+            Sensor,
+            sensor_groups(),
+            ActiveEvents::COLLISION_EVENTS,
+            SpriteBundle {
+                texture: asset_server.load(get_item_sprite(&item)),
+                transform: Transform::from_translation(spawn_location.extend(0.)),
+                ..default()
+            },
+            YSort(0.),
+            GameDespawn,
+        ))
+        .id()
+}
+
 fn cleanup_entity_chunks(
     mut commands: Commands,
     chunks_query: Query<Entity, With<EntityChunk>>,
     mut current_entity_chunks: ResMut<CurrentEntityChunks>,
+    mut chunk_memory: ResMut<ChunkMemory>,
+    mut world_seed: ResMut<WorldSeed>,
 ) {
-    current_entity_chunks.0.clear();
+    *current_entity_chunks = CurrentEntityChunks::default();
+    *chunk_memory = ChunkMemory::default();
+    world_seed.0 = thread_rng().gen();
 
     for entity in chunks_query.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
 
+// Tiles are tinted by the world seed, which changes with every run
+fn cleanup_tile_chunks(
+    mut current_tile_chunks: ResMut<CurrentTileChunks>,
+    mut tile_pool: ResMut<TilePool>,
+    mut tiles_query: Query<TileParts, (With<TileChunk>, Without<Player>)>,
+) {
+    current_tile_chunks.center = None;
+
+    for (_, tiles) in current_tile_chunks.chunks.drain() {
+        for (layer, entity) in tiles {
+            release_tile(&mut tile_pool, &mut tiles_query, layer, entity);
+        }
+    }
+}
+
+fn cycle_render_distance(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut render_distance: ResMut<RenderDistance>,
+) {
+    if keys.just_pressed(KeyCode::KeyV) {
+        let index = RENDER_DISTANCES
+            .iter()
+            .position(|distance| *distance == render_distance.x)
+            .map_or(0, |index| (index + 1) % RENDER_DISTANCES.len());
+        render_distance.x = RENDER_DISTANCES[index];
+        render_distance.y = RENDER_DISTANCES[index];
+    }
+}
+
+// Layers scroll with the wind, turned by how far their angle is from the base layer's
 fn update_offset(
-    time: Res<Time>,
+    wind: Res<Wind>,
     mut offsets_query: Query<(&Chunk, &AnimateOffset, &mut Transform)>,
 ) {
     for (chunk, animate_offset, mut transform) in offsets_query.iter_mut() {
         let pos = chunk.pos.as_vec2() * CHUNK_SIZE;
 
-        let mut pos_offset =
-            Vec2::from_angle(animate_offset.angle) * animate_offset.speed * time.elapsed_seconds();
+        let mut pos_offset = Vec2::from_angle(animate_offset.angle - 0.25 * PI).rotate(wind.scroll)
+            * animate_offset.speed;
         pos_offset.x %= TILE_SIZE;
         pos_offset.y %= TILE_SIZE;
         pos_offset = pos_offset.trunc();
@@ -401,3 +798,38 @@ fn handle_item_pickups(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rolls(world_seed: &WorldSeed, chunk: IVec2) -> Vec<u64> {
+        let mut rng = chunk_rng(world_seed, chunk);
+        (0..8).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    fn same_chunk_rolls_the_same() {
+        let world_seed = WorldSeed(42);
+        let chunk = IVec2::new(3, -7);
+        assert_eq!(rolls(&world_seed, chunk), rolls(&world_seed, chunk));
+    }
+
+    #[test]
+    fn different_chunks_roll_differently() {
+        let world_seed = WorldSeed(42);
+        let chunks = [
+            IVec2::new(0, 0),
+            IVec2::new(1, 0),
+            IVec2::new(0, 1),
+            IVec2::new(-1, 0),
+            IVec2::new(0, -1),
+            IVec2::new(1, 1),
+        ];
+        for (i, a) in chunks.iter().enumerate() {
+            for b in chunks.iter().skip(i + 1) {
+                assert_ne!(rolls(&world_seed, *a), rolls(&world_seed, *b));
+            }
+        }
+    }
+}
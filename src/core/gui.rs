@@ -1,11 +1,17 @@
 use bevy::prelude::*;
+use bevy_tweening::{lens::TextColorLens, Animator, EaseMethod, Tween};
+use std::time::Duration;
 
 use crate::core::{GameState, PauseState};
 
 use super::{
-    items::{get_item_sprite, Inventory, INVENTORY_SIZE},
+    enemies::{bosses::Boss, director::Director, Health},
+    environment::RenderDistance,
+    items::{get_item_sprite, Inventory, SlotTiers, INVENTORY_SIZE},
+    obstacles::CollisionMode,
     player::{level_required_xp, Leveling},
-    GameStats, IngameTime,
+    ports::Gold,
+    GameStats, IngameTime, TweenDespawn,
 };
 
 pub struct GuiPlugin;
@@ -13,12 +19,17 @@ pub struct GuiPlugin;
 impl Plugin for GuiPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ItemSprites(Vec::new()))
+            .add_event::<ShowBanner>()
             .add_systems(OnEnter(GameState::StartMenu), setup_start_menu)
             .add_systems(OnExit(GameState::StartMenu), cleanup_start_menu)
+            .add_systems(
+                Update,
+                update_option_texts.run_if(in_state(GameState::StartMenu)),
+            )
             .add_systems(OnEnter(GameState::Game), (setup_items_gui, setup_upper_gui))
             .add_systems(
                 OnExit(GameState::Game),
-                (cleanup_items_gui, cleanup_upper_gui),
+                (cleanup_items_gui, cleanup_upper_gui, cleanup_boss_bar),
             )
             .add_systems(OnEnter(GameState::GameOver), (setup_stats_menu))
             .add_systems(OnExit(GameState::GameOver), (cleanup_stats_menu))
@@ -26,7 +37,14 @@ impl Plugin for GuiPlugin {
             .add_systems(OnExit(PauseState::Paused), (cleanup_pause_menu))
             .add_systems(
                 Update,
-                (update_items_gui, update_xp_gui, update_timer_gui)
+                (
+                    update_items_gui,
+                    update_xp_gui,
+                    update_timer_gui,
+                    update_gold_gui,
+                    update_boss_bar,
+                    show_banners,
+                )
                     .run_if(in_state(GameState::Game))
                     .run_if(in_state(PauseState::Running)),
             );
@@ -36,6 +54,14 @@ impl Plugin for GuiPlugin {
 #[derive(Resource)]
 struct ItemSprites(Vec<Entity>);
 
+// Part tint for each slot tier
+const TIER_COLORS: [Color; 4] = [
+    Color::WHITE,
+    Color::rgb(0.6, 1., 0.6),
+    Color::rgb(0.6, 0.8, 1.),
+    Color::rgb(1., 0.8, 0.4),
+];
+
 #[derive(Component)]
 struct ItemsContainer;
 
@@ -120,17 +146,24 @@ fn cleanup_items_gui(
 fn update_items_gui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut item_sprites_query: Query<(Option<&mut UiImage>, &mut Visibility), With<ItemSprite>>,
+    mut item_sprites_query: Query<
+        (Option<&mut UiImage>, &mut Visibility, &mut BackgroundColor),
+        With<ItemSprite>,
+    >,
     inventory: Res<Inventory>,
+    slot_tiers: Res<SlotTiers>,
     item_sprites: Res<ItemSprites>,
 ) {
     for (index, item_box) in item_sprites.0.iter().enumerate() {
         let item = inventory.0.get(index);
 
-        let Ok((item_image, mut item_visibility)) = item_sprites_query.get_mut(*item_box) else {
+        let Ok((item_image, mut item_visibility, mut item_color)) =
+            item_sprites_query.get_mut(*item_box)
+        else {
             // Items not setup yet
             continue;
         };
+        *item_color = TIER_COLORS[slot_tiers.0[index] as usize].into();
 
         if let Some(item) = item {
             let texture: Handle<Image> = asset_server.load(get_item_sprite(item));
@@ -161,6 +194,9 @@ struct UpperGuiContainer;
 #[derive(Component)]
 struct TimerGui;
 
+#[derive(Component)]
+struct GoldGui;
+
 fn setup_upper_gui(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .spawn((
@@ -232,6 +268,17 @@ fn setup_upper_gui(mut commands: Commands, asset_server: Res<AssetServer>) {
                             ..default()
                         },
                     ));
+                    parent.spawn((
+                        GoldGui,
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: asset_server.load("fonts/pixel_font.ttf"),
+                                font_size: 28.,
+                                color: Color::rgb(1., 0.85, 0.2),
+                            },
+                        ),
+                    ));
                 });
         });
 }
@@ -265,6 +312,15 @@ fn update_timer_gui(
         },
     );
 }
+
+fn update_gold_gui(gold: Res<Gold>, mut gold_gui_query: Query<&mut Text, With<GoldGui>>) {
+    let Ok(mut gold_gui) = gold_gui_query.get_single_mut() else {
+        return;
+    };
+
+    gold_gui.sections[0].value = format!("{} gold", gold.0);
+}
+
 fn cleanup_upper_gui(
     mut commands: Commands,
     upper_gui_query: Query<Entity, With<UpperGuiContainer>>,
@@ -276,6 +332,173 @@ fn cleanup_upper_gui(
     commands.entity(upper_gui).despawn_recursive();
 }
 
+#[derive(Event)]
+pub struct ShowBanner(pub String);
+
+#[derive(Component)]
+struct Banner;
+
+const BANNER_DURATION: f32 = 3.;
+
+fn show_banners(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut ev_show_banner: EventReader<ShowBanner>,
+    banner_query: Query<Entity, With<Banner>>,
+) {
+    for event in ev_show_banner.read() {
+        for banner in banner_query.iter() {
+            commands.entity(banner).despawn_recursive();
+        }
+
+        let text_color = Color::rgb(1., 0.85, 0.3);
+
+        let fade_tween = Tween::new(
+            EaseMethod::Linear,
+            Duration::from_secs_f32(BANNER_DURATION),
+            TextColorLens {
+                start: text_color,
+                end: text_color.with_a(0.),
+                section: 0,
+            },
+        )
+        .with_completed_event(0);
+
+        commands.spawn((
+            Banner,
+            TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(30.),
+                    width: Val::Percent(100.),
+                    ..default()
+                },
+                text: Text::from_section(
+                    event.0.clone(),
+                    TextStyle {
+                        font: asset_server.load("fonts/pixel_font.ttf"),
+                        font_size: 56.,
+                        color: text_color,
+                    },
+                )
+                .with_justify(JustifyText::Center),
+                ..default()
+            },
+            Animator::new(fade_tween),
+            TweenDespawn,
+        ));
+    }
+}
+
+#[derive(Component)]
+struct BossBarContainer;
+
+#[derive(Component)]
+struct BossBar;
+
+fn update_boss_bar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    boss_query: Query<(&Boss, &Health)>,
+    boss_bar_container_query: Query<Entity, With<BossBarContainer>>,
+    mut boss_bar_query: Query<&mut Style, With<BossBar>>,
+) {
+    let Ok((boss, boss_health)) = boss_query.get_single() else {
+        for boss_bar_container in boss_bar_container_query.iter() {
+            commands.entity(boss_bar_container).despawn_recursive();
+        }
+        return;
+    };
+
+    let health_percent = (boss_health.health / boss_health.max_health).max(0.) * 100.;
+
+    if let Ok(mut boss_bar_style) = boss_bar_query.get_single_mut() {
+        boss_bar_style.width = Val::Percent(health_percent);
+        return;
+    }
+
+    if boss_bar_container_query.get_single().is_ok() {
+        return;
+    }
+
+    commands
+        .spawn((
+            BossBarContainer,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Px(176.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        max_width: Val::Px(768.),
+                        padding: UiRect::horizontal(Val::Px(24.)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(8.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle {
+                        text: Text::from_section(
+                            boss.name.clone(),
+                            TextStyle {
+                                font: asset_server.load("fonts/pixel_font.ttf"),
+                                font_size: 28.,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        ..default()
+                    });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                border: UiRect::all(Val::Px(4.)),
+                                width: Val::Percent(100.),
+                                ..default()
+                            },
+                            border_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                            background_color: Color::rgba(0., 0., 0., 0.5).into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                BossBar,
+                                NodeBundle {
+                                    style: Style {
+                                        width: Val::Percent(health_percent),
+                                        height: Val::Px(24.),
+                                        ..default()
+                                    },
+                                    background_color: Color::rgb(0.8, 0., 0.).into(),
+                                    ..default()
+                                },
+                            ));
+                        });
+                });
+        });
+}
+
+fn cleanup_boss_bar(
+    mut commands: Commands,
+    boss_bar_container_query: Query<Entity, With<BossBarContainer>>,
+) {
+    for boss_bar_container in boss_bar_container_query.iter() {
+        commands.entity(boss_bar_container).despawn_recursive();
+    }
+}
+
 #[derive(Component)]
 struct StartMenu;
 
@@ -350,6 +573,9 @@ fn setup_start_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 .spawn(NodeBundle {
                                     style: Style {
                                         padding: UiRect::all(Val::Px(20.)),
+                                        flex_direction: FlexDirection::Column,
+                                        align_items: AlignItems::Center,
+                                        row_gap: Val::Px(12.),
                                         ..default()
                                     },
                                     ..default()
@@ -366,12 +592,78 @@ fn setup_start_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                                         ),
                                         ..default()
                                     });
+                                    for option in [
+                                        MenuOption::ClassicMode,
+                                        MenuOption::SolidObstacles,
+                                        MenuOption::ViewDistance,
+                                    ] {
+                                        spawn_option_text(parent, &asset_server, option);
+                                    }
                                 });
                         });
                 });
         });
 }
 
+// A start menu setting toggled with a key
+#[derive(Component, Clone, Copy)]
+enum MenuOption {
+    ClassicMode,
+    SolidObstacles,
+    ViewDistance,
+}
+
+fn spawn_option_text(parent: &mut ChildBuilder, asset_server: &AssetServer, option: MenuOption) {
+    parent.spawn((
+        option,
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/pixel_font.ttf"),
+                    font_size: 20.,
+                    color: Color::BLACK,
+                },
+            ),
+            ..default()
+        },
+    ));
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+fn format_option(
+    option: MenuOption,
+    director: &Director,
+    collision_mode: &CollisionMode,
+    render_distance: &RenderDistance,
+) -> String {
+    let (label, value, key) = match option {
+        MenuOption::ClassicMode => ("Classic Mode", on_off(!director.enabled).into(), "C"),
+        MenuOption::SolidObstacles => ("Solid Obstacles", on_off(collision_mode.solid).into(), "O"),
+        MenuOption::ViewDistance => ("View Distance", render_distance.x.to_string(), "V"),
+    };
+    format!("{}: {} (Press {})", label, value, key)
+}
+
+fn update_option_texts(
+    director: Res<Director>,
+    collision_mode: Res<CollisionMode>,
+    render_distance: Res<RenderDistance>,
+    mut option_text_query: Query<(&MenuOption, &mut Text)>,
+) {
+    for (option, mut text) in option_text_query.iter_mut() {
+        text.sections[0].value =
+            format_option(*option, &director, &collision_mode, &render_distance);
+    }
+}
+
 fn cleanup_start_menu(mut commands: Commands, start_menu_query: Query<Entity, With<StartMenu>>) {
     let Ok(start_menu) = start_menu_query.get_single() else {
         return;
@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::{
+    biomes::value_noise,
+    enemies::{ContactEnemy, DamageEvent, DamageKind, Enemy, EnemyKnockback},
+    gui::ShowBanner,
+    obstacles::sensor_groups,
+    player::Player,
+    textures::radial_texture,
+    GameDespawn, GameState, Movement, PauseState, YSort,
+};
+
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HazardTextures>().add_systems(
+            Update,
+            (
+                update_charybdis,
+                pull_into_whirlpools.after(update_charybdis),
+                slow_on_reefs,
+            )
+                .run_if(in_state(GameState::Game))
+                .run_if(in_state(PauseState::Running)),
+        );
+    }
+}
+
+const HAZARD_TEXTURE_SIZE: u32 = 64;
+
+const WHIRLPOOL_RADIUS: f32 = 220.;
+const WHIRLPOOL_CORE: f32 = 32.;
+// Accelerations at the center, falling off to nothing at the rim
+const WHIRLPOOL_PULL: f32 = 250.;
+const WHIRLPOOL_SWIRL: f32 = 120.;
+const WHIRLPOOL_DAMAGE: f32 = 40.;
+const WHIRLPOOL_KNOCKBACK: f32 = 300.;
+const WHIRLPOOL_SPIN: f32 = 1.5;
+const WHIRLPOOL_COLOR: Color = Color::rgba(0.7, 0.85, 1., 0.8);
+// Seconds between hits on whatever sits in the core
+const CORE_TICK: f32 = 0.5;
+
+const REEF_RADIUS: f32 = 120.;
+// Share of speed lost per second over the reef
+const REEF_SLOW: f32 = 2.5;
+const REEF_COLOR: Color = Color::rgba(1., 0.7, 0.6, 0.6);
+const REEF_SALT: u64 = 0x3C6E_F372_FE94_F82B;
+
+const CHARYBDIS_RADIUS: f32 = 700.;
+const CHARYBDIS_CORE: f32 = 120.;
+const CHARYBDIS_PULL: f32 = 350.;
+const CHARYBDIS_SWIRL: f32 = 200.;
+const CHARYBDIS_DAMAGE: f32 = 200.;
+const CHARYBDIS_KNOCKBACK: f32 = 600.;
+const CHARYBDIS_COLOR: Color = Color::rgba(0.5, 0.6, 0.8, 0.9);
+// She wakes when the player sails this close
+const CHARYBDIS_TRIGGER: f32 = 800.;
+const CHARYBDIS_DURATION: f32 = 15.;
+// Seconds to open up and to close again
+const CHARYBDIS_RAMP: f32 = 2.;
+
+#[derive(Resource)]
+pub struct HazardTextures {
+    whirlpool: Handle<Image>,
+    reef: Handle<Image>,
+}
+
+impl FromWorld for HazardTextures {
+    fn from_world(world: &mut World) -> Self {
+        let mut images = world.resource_mut::<Assets<Image>>();
+
+        HazardTextures {
+            // Spiral arms fading out towards the rim
+            whirlpool: images.add(hazard_texture(|offset, distance| {
+                let arms = (3. * offset.to_angle() + 10. * distance).sin();
+                (0.5 + 0.5 * arms) * (1. - distance)
+            })),
+            // Patches of coral under the surface
+            reef: images.add(hazard_texture(|offset, distance| {
+                let coral = value_noise(REEF_SALT, (offset + 1.) * 4.);
+                let alpha = if coral > 0.5 { 0.8 } else { 0.3 };
+                alpha * (1. - distance.powi(4))
+            })),
+        }
+    }
+}
+
+// Sucks in everything with Movement and grinds up whatever reaches the core
+#[derive(Component)]
+pub struct Whirlpool {
+    radius: f32,
+    pull: f32,
+    swirl: f32,
+    core: f32,
+    damage: f32,
+    // Scales the whole whirlpool while it opens and closes
+    strength: f32,
+    tick: f32,
+}
+
+#[derive(Component)]
+pub struct Reef {
+    radius: f32,
+}
+
+// A whirlpool that lies dormant until the player comes near, then opens for a while and is gone
+#[derive(Component)]
+pub struct Charybdis {
+    elapsed: Option<f32>,
+}
+
+// Round, clear outside the circle
+fn hazard_texture(alpha: impl Fn(Vec2, f32) -> f32) -> Image {
+    radial_texture(HAZARD_TEXTURE_SIZE, |offset, distance| {
+        if distance < 1. {
+            alpha(offset, distance)
+        } else {
+            0.
+        }
+    })
+}
+
+fn whirlpool_core(core: f32, knockback: f32) -> impl Bundle {
+    (
+        ContactEnemy,
+        Collider::ball(core),
+        Sensor,
+        sensor_groups(),
+        ActiveEvents::COLLISION_EVENTS,
+        EnemyKnockback { knockback },
+    )
+}
+
+pub fn spawn_whirlpool(
+    commands: &mut Commands,
+    hazard_textures: &HazardTextures,
+    spawn_location: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            Whirlpool {
+                radius: WHIRLPOOL_RADIUS,
+                pull: WHIRLPOOL_PULL,
+                swirl: WHIRLPOOL_SWIRL,
+                core: WHIRLPOOL_CORE,
+                damage: WHIRLPOOL_DAMAGE,
+                strength: 1.,
+                tick: CORE_TICK,
+            },
+            whirlpool_core(WHIRLPOOL_CORE, WHIRLPOOL_KNOCKBACK),
+            SpriteBundle {
+                texture: hazard_textures.whirlpool.clone(),
+                transform: Transform::from_translation(spawn_location.extend(0.)),
+                sprite: Sprite {
+                    color: WHIRLPOOL_COLOR,
+                    custom_size: Some(Vec2::splat(WHIRLPOOL_RADIUS * 2.)),
+                    ..default()
+                },
+                ..default()
+            },
+            YSort(-1.),
+            GameDespawn,
+        ))
+        .id()
+}
+
+pub fn spawn_reef(
+    commands: &mut Commands,
+    hazard_textures: &HazardTextures,
+    spawn_location: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            Reef {
+                radius: REEF_RADIUS,
+            },
+            SpriteBundle {
+                texture: hazard_textures.reef.clone(),
+                transform: Transform::from_translation(spawn_location.extend(0.)),
+                sprite: Sprite {
+                    color: REEF_COLOR,
+                    custom_size: Some(Vec2::splat(REEF_RADIUS * 2.)),
+                    ..default()
+                },
+                ..default()
+            },
+            YSort(-1.),
+            GameDespawn,
+        ))
+        .id()
+}
+
+pub fn spawn_charybdis(
+    commands: &mut Commands,
+    hazard_textures: &HazardTextures,
+    spawn_location: Vec2,
+) -> Entity {
+    commands
+        .spawn((
+            Charybdis { elapsed: None },
+            Whirlpool {
+                radius: CHARYBDIS_RADIUS,
+                pull: CHARYBDIS_PULL,
+                swirl: CHARYBDIS_SWIRL,
+                core: CHARYBDIS_CORE,
+                damage: CHARYBDIS_DAMAGE,
+                strength: 0.,
+                tick: CORE_TICK,
+            },
+            SpriteBundle {
+                texture: hazard_textures.whirlpool.clone(),
+                transform: Transform::from_translation(spawn_location.extend(0.)),
+                sprite: Sprite {
+                    color: CHARYBDIS_COLOR,
+                    custom_size: Some(Vec2::ZERO),
+                    ..default()
+                },
+                ..default()
+            },
+            YSort(-1.),
+            GameDespawn,
+        ))
+        .id()
+}
+
+fn update_charybdis(
+    mut commands: Commands,
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Player>>,
+    mut charybdis_query: Query<(Entity, &mut Charybdis, &mut Whirlpool, &Transform)>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    for (entity, mut charybdis, mut whirlpool, transform) in charybdis_query.iter_mut() {
+        let Some(elapsed) = charybdis.elapsed.as_mut() else {
+            let distance = transform
+                .translation
+                .xy()
+                .distance(player_transform.translation.xy());
+            if distance < CHARYBDIS_TRIGGER {
+                charybdis.elapsed = Some(0.);
+                commands
+                    .entity(entity)
+                    .insert(whirlpool_core(CHARYBDIS_CORE, CHARYBDIS_KNOCKBACK));
+                ev_show_banner.send(ShowBanner("Charybdis stirs".into()));
+            }
+            continue;
+        };
+
+        *elapsed += time.delta_seconds();
+        if *elapsed >= CHARYBDIS_DURATION {
+            // Despawned while loaded, so the chunk remembers she is spent
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        whirlpool.strength = (*elapsed / CHARYBDIS_RAMP)
+            .min((CHARYBDIS_DURATION - *elapsed) / CHARYBDIS_RAMP)
+            .min(1.);
+    }
+}
+
+fn pull_into_whirlpools(
+    time: Res<Time>,
+    mut whirlpool_query: Query<(&mut Whirlpool, &mut Transform, &mut Sprite), Without<Movement>>,
+    mut movement_query: Query<(Entity, &mut Movement, &Transform, Has<Enemy>)>,
+    mut ev_damage: EventWriter<DamageEvent>,
+) {
+    for (mut whirlpool, mut transform, mut sprite) in whirlpool_query.iter_mut() {
+        transform.rotate_z(-WHIRLPOOL_SPIN * whirlpool.strength * time.delta_seconds());
+        sprite.custom_size = Some(Vec2::splat(whirlpool.radius * 2. * whirlpool.strength));
+
+        if whirlpool.strength <= 0. {
+            continue;
+        }
+
+        whirlpool.tick -= time.delta_seconds();
+        let grind = whirlpool.tick <= 0.;
+        if grind {
+            whirlpool.tick += CORE_TICK;
+        }
+
+        let center = transform.translation.xy();
+        let radius = whirlpool.radius * whirlpool.strength;
+
+        for (entity, mut movement, movement_transform, is_enemy) in movement_query.iter_mut() {
+            let offset = center - movement_transform.translation.xy();
+            let distance = offset.length();
+            if distance > radius {
+                continue;
+            }
+
+            let falloff = (1. - distance / radius) * whirlpool.strength;
+            let inward = offset.normalize_or_zero();
+            movement.velocity += (inward * whirlpool.pull + inward.perp() * whirlpool.swirl)
+                * falloff
+                * time.delta_seconds();
+
+            // The player is hurt through the core's contact collider instead
+            if grind && is_enemy && distance < whirlpool.core {
+                ev_damage.send(DamageEvent {
+                    damage: whirlpool.damage,
+                    entity,
+                    kind: DamageKind::Physical,
+                });
+            }
+        }
+    }
+}
+
+fn slow_on_reefs(
+    time: Res<Time>,
+    reef_query: Query<(&Reef, &Transform), Without<Movement>>,
+    mut movement_query: Query<(&mut Movement, &Transform)>,
+) {
+    let slow = (1. - REEF_SLOW * time.delta_seconds()).max(0.);
+
+    for (reef, reef_transform) in reef_query.iter() {
+        let center = reef_transform.translation.xy();
+
+        for (mut movement, transform) in movement_query.iter_mut() {
+            if transform.translation.xy().distance(center) < reef.radius {
+                movement.velocity *= slow;
+            }
+        }
+    }
+}
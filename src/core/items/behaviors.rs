@@ -4,12 +4,16 @@ use bevy_tweening::{lens::SpriteColorLens, Animator, EaseMethod, Tween};
 use std::{collections::HashSet, time::Duration};
 
 use crate::core::{
-    enemies::{ContactEnemy, DamageEvent, Enemy, Health, Targetable},
+    enemies::{ContactEnemy, DamageEvent, DamageKind, Enemy, Health, Targetable},
+    obstacles::{hits_solid, sensor_groups},
     GameDespawn, GameState, Movement, PauseState, TweenDespawn, YSort,
 };
 
 pub struct ProjectileBehaviorsPlugin;
 
+// Projectiles stop once their tip is this deep in a solid
+const SOLID_STOP_RADIUS: f32 = 4.;
+
 impl Plugin for ProjectileBehaviorsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
@@ -20,6 +24,7 @@ impl Plugin for ProjectileBehaviorsPlugin {
                 handle_fire,
                 handle_weapon_collisions,
                 handle_fire_collisions,
+                stop_on_solids,
             )
                 .run_if(in_state(GameState::Game))
                 .run_if(in_state(PauseState::Running)),
@@ -31,6 +36,7 @@ impl Plugin for ProjectileBehaviorsPlugin {
 pub struct ContactWeapon {
     pub pierce: i32,
     pub damage: f32,
+    pub kind: DamageKind,
 }
 
 #[derive(Component)]
@@ -68,6 +74,7 @@ fn handle_fire(
                 ev_damage.send(DamageEvent {
                     damage: fire.damage,
                     entity: *enemy_entity,
+                    kind: DamageKind::Fire,
                 });
             }
         }
@@ -99,6 +106,66 @@ fn handle_spear(time: Res<Time>, mut spear_query: Query<(&mut Transform, &SpearB
     }
 }
 
+fn spawn_fire(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    bomb_behavior: &BombBehavior,
+    translation: Vec3,
+) {
+    commands.spawn((
+        Collider::ball(64.),
+        Sensor,
+        sensor_groups(),
+        ActiveCollisionTypes::STATIC_STATIC,
+        ActiveEvents::COLLISION_EVENTS,
+        FireBehavior {
+            lifetime: 3.,
+            damage: bomb_behavior.damage,
+            contact: HashSet::new(),
+            timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+        },
+        SpriteBundle {
+            texture: asset_server.load("sprites/projectiles/greek_fire.png"),
+            transform: Transform {
+                translation,
+                scale: Vec3::splat(bomb_behavior.scale),
+                ..default()
+            },
+            ..default()
+        },
+        YSort(-1.),
+        GameDespawn,
+    ));
+}
+
+type FlyingProjectile = Or<(With<SpearBehavior>, With<HomingBehavior>)>;
+
+// Bombs burst into fire where they hit, everything else just stops
+fn stop_on_solids(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    rapier_context: Res<RapierContext>,
+    projectile_query: Query<(Entity, &Transform, Option<&BombBehavior>), FlyingProjectile>,
+) {
+    for (entity, transform, bomb_behavior) in projectile_query.iter() {
+        if hits_solid(
+            &rapier_context,
+            transform.translation.xy(),
+            SOLID_STOP_RADIUS,
+        ) {
+            commands.entity(entity).despawn_recursive();
+            if let Some(bomb_behavior) = bomb_behavior {
+                spawn_fire(
+                    &mut commands,
+                    &asset_server,
+                    bomb_behavior,
+                    transform.translation,
+                );
+            }
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct HomingBehavior {
     pub acceleration: f32,
@@ -210,34 +277,18 @@ fn handle_weapon_collisions(
                     }
 
                     if let Some(bomb_behavior) = bomb_behavior {
-                        commands.spawn((
-                            Collider::ball(64.),
-                            Sensor,
-                            ActiveCollisionTypes::STATIC_STATIC,
-                            ActiveEvents::COLLISION_EVENTS,
-                            FireBehavior {
-                                lifetime: 3.,
-                                damage: bomb_behavior.damage,
-                                contact: HashSet::new(),
-                                timer: Timer::from_seconds(0.5, TimerMode::Repeating),
-                            },
-                            SpriteBundle {
-                                texture: asset_server.load("sprites/projectiles/greek_fire.png"),
-                                transform: Transform {
-                                    translation: enemy_transform.translation,
-                                    scale: Vec3::splat(bomb_behavior.scale),
-                                    ..default()
-                                },
-                                ..default()
-                            },
-                            YSort(-1.),
-                            GameDespawn,
-                        ));
+                        spawn_fire(
+                            &mut commands,
+                            &asset_server,
+                            bomb_behavior,
+                            enemy_transform.translation,
+                        );
                     }
 
                     ev_damage.send(DamageEvent {
                         damage: weapon.damage,
                         entity: enemy_entity,
+                        kind: weapon.kind,
                     });
                 };
             }
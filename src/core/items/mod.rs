@@ -14,8 +14,11 @@ use crate::core::{GameState, PauseState};
 use self::behaviors::{BombBehavior, ContactWeapon, HomingBehavior, HomingRotation, SpearBehavior};
 
 use super::{
-    enemies::{DamageEvent, Enemy, Targetable},
+    enemies::{DamageEvent, DamageKind, Enemy, Targetable},
+    obstacles::sensor_groups,
     player::{Leveling, Player},
+    weather::Weather,
+    wind::Drift,
     GameDespawn, IngameTime, Movement, TimedDespawn, TweenDespawn, YSort,
 };
 
@@ -41,15 +44,23 @@ impl Inventory {
     }
 }
 
+// Tiers belong to the hull slot rather than the part mounted in it
+#[derive(Resource, Default)]
+pub struct SlotTiers(pub [u32; INVENTORY_SIZE]);
+
 #[derive(Resource)]
 pub struct ItemCooldowns(pub HashMap<Item, f32>);
 
 impl Plugin for ItemsPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Inventory::default())
+            .insert_resource(SlotTiers::default())
             .insert_resource(ItemCooldowns(HashMap::new()))
             .add_plugins(behaviors::ProjectileBehaviorsPlugin)
-            .add_systems(OnEnter(GameState::Game), (reset_cooldowns, reset_inventory))
+            .add_systems(
+                OnEnter(GameState::Game),
+                (reset_cooldowns, reset_inventory, reset_slot_tiers),
+            )
             .add_systems(
                 Update,
                 trigger_weapons
@@ -60,6 +71,17 @@ impl Plugin for ItemsPlugin {
 }
 
 pub const INVENTORY_SIZE: usize = 3;
+pub const MAX_TIER: u32 = 3;
+
+pub fn get_item_name(item: &Item) -> &'static str {
+    match item {
+        Item::Spear => "Spear",
+        Item::Bow => "Bow",
+        Item::GreekFire => "Greek Fire",
+        Item::PoseidonTrident => "Poseidon's Trident",
+        Item::ZeusThunderbolt => "Zeus' Thunderbolt",
+    }
+}
 
 pub fn get_item_sprite(item: &Item) -> &'static str {
     match item {
@@ -71,7 +93,7 @@ pub fn get_item_sprite(item: &Item) -> &'static str {
     }
 }
 
-const ATTACK_RANGE: f32 = 400.;
+pub const ATTACK_RANGE: f32 = 400.;
 const BOW_COOLDOWN: f32 = 0.15;
 const BOW_SPRAY: f32 = PI / 6.;
 const SPEAR_COOLDOWN: f32 = 1.;
@@ -84,13 +106,17 @@ pub fn trigger_weapons(
     asset_server: Res<AssetServer>,
     ingame_time: Res<IngameTime>,
     inventory: Res<Inventory>,
+    slot_tiers: Res<SlotTiers>,
     mut item_cooldowns: ResMut<ItemCooldowns>,
     player_query: Query<(&Transform, &Leveling), With<Player>>,
     enemies_query: Query<(&Transform, Entity), (With<Enemy>, With<Targetable>, Without<Player>)>,
     mut ev_damage: EventWriter<DamageEvent>,
+    weather: Res<Weather>,
 ) {
     let (player_transform, player_leveling) = player_query.get_single().unwrap();
     let player_pos = player_transform.translation.xy();
+    // Fog hides anything further out
+    let attack_range = ATTACK_RANGE * weather.visibility();
 
     let mut nearest_enemies = enemies_query
         .iter()
@@ -108,10 +134,11 @@ pub fn trigger_weapons(
 
     let mut item_count: HashMap<Item, i32> = HashMap::new();
 
-    for item in inventory.0.iter() {
+    for (slot, item) in inventory.0.iter().enumerate() {
         let prev = item_count.entry(*item).or_insert(0);
 
-        *prev += 1;
+        // Each tier counts as another copy of the part
+        *prev += 1 + slot_tiers.0[slot] as i32;
     }
 
     for (item, count) in item_count {
@@ -123,7 +150,7 @@ pub fn trigger_weapons(
 
                 let nearest_enemy_pos = nearest_enemy.0;
 
-                if nearest_enemy_pos.distance(player_pos) > ATTACK_RANGE {
+                if nearest_enemy_pos.distance(player_pos) > attack_range {
                     continue;
                 };
 
@@ -142,11 +169,13 @@ pub fn trigger_weapons(
                 commands.spawn((
                     Collider::cuboid(32.0, 1.0),
                     Sensor,
+                    sensor_groups(),
                     ActiveCollisionTypes::STATIC_STATIC,
                     ActiveEvents::COLLISION_EVENTS,
                     ContactWeapon {
                         pierce: 2 + player_leveling.buff,
                         damage: 25. * player_leveling.damage_multiplier,
+                        kind: DamageKind::Physical,
                     },
                     SpearBehavior {
                         angle: throw_angle,
@@ -173,7 +202,7 @@ pub fn trigger_weapons(
 
                 let nearest_enemy_pos = nearest_enemy.0;
 
-                if nearest_enemy_pos.distance(player_pos) > ATTACK_RANGE {
+                if nearest_enemy_pos.distance(player_pos) > attack_range {
                     continue;
                 };
 
@@ -195,16 +224,20 @@ pub fn trigger_weapons(
                     commands.spawn((
                         Collider::cuboid(32.0, 1.0),
                         Sensor,
+                        sensor_groups(),
                         ActiveCollisionTypes::STATIC_STATIC,
                         ActiveEvents::COLLISION_EVENTS,
                         ContactWeapon {
                             pierce: 0,
                             damage: 5. * player_leveling.damage_multiplier,
+                            kind: DamageKind::Physical,
                         },
                         SpearBehavior {
                             angle: throw_angle,
                             speed: 1250.,
                         },
+                        // Light enough to be blown off course
+                        Drift(0.5),
                         SpriteBundle {
                             texture: asset_server.load("sprites/projectiles/arrow.png"),
                             transform: Transform {
@@ -227,7 +260,7 @@ pub fn trigger_weapons(
 
                 let nearest_enemy_pos = nearest_enemy.0;
 
-                if nearest_enemy_pos.distance(player_pos) > ATTACK_RANGE {
+                if nearest_enemy_pos.distance(player_pos) > attack_range {
                     continue;
                 };
 
@@ -246,11 +279,13 @@ pub fn trigger_weapons(
                 commands.spawn((
                     Collider::ball(16.),
                     Sensor,
+                    sensor_groups(),
                     ActiveCollisionTypes::STATIC_STATIC,
                     ActiveEvents::COLLISION_EVENTS,
                     ContactWeapon {
                         pierce: 0,
                         damage: 15. * player_leveling.damage_multiplier,
+                        kind: DamageKind::Fire,
                     },
                     SpearBehavior {
                         angle: throw_angle,
@@ -280,7 +315,7 @@ pub fn trigger_weapons(
 
                 let nearest_enemy_pos = nearest_enemy.0;
 
-                if nearest_enemy_pos.distance(player_pos) > ATTACK_RANGE {
+                if nearest_enemy_pos.distance(player_pos) > attack_range {
                     continue;
                 };
 
@@ -299,11 +334,13 @@ pub fn trigger_weapons(
                 commands.spawn((
                     Collider::cuboid(32.0, 14.0),
                     Sensor,
+                    sensor_groups(),
                     ActiveCollisionTypes::STATIC_STATIC,
                     ActiveEvents::COLLISION_EVENTS,
                     ContactWeapon {
                         pierce: 5 + player_leveling.buff,
                         damage: 25. * player_leveling.damage_multiplier,
+                        kind: DamageKind::Physical,
                     },
                     Movement {
                         velocity: Vec2::ZERO,
@@ -335,7 +372,7 @@ pub fn trigger_weapons(
 
                 let nearest_enemy_pos = nearest_enemy.0;
 
-                if nearest_enemy_pos.distance(player_pos) > ATTACK_RANGE {
+                if nearest_enemy_pos.distance(player_pos) > attack_range {
                     continue;
                 };
 
@@ -356,13 +393,14 @@ pub fn trigger_weapons(
                         continue;
                     };
 
-                    if nearest_enemy.0.distance(player_pos) > ATTACK_RANGE {
+                    if nearest_enemy.0.distance(player_pos) > attack_range {
                         continue;
                     };
 
                     ev_damage.send(DamageEvent {
                         damage: 100. * player_leveling.damage_multiplier,
                         entity: nearest_enemy.1,
+                        kind: DamageKind::Lightning,
                     });
 
                     let fade_tween = Tween::new(
@@ -406,3 +444,7 @@ fn reset_cooldowns(mut item_cooldowns: ResMut<ItemCooldowns>) {
 fn reset_inventory(mut inventory: ResMut<Inventory>) {
     *inventory = Inventory::default();
 }
+
+fn reset_slot_tiers(mut slot_tiers: ResMut<SlotTiers>) {
+    *slot_tiers = SlotTiers::default();
+}
@@ -1,15 +1,29 @@
 use bevy::{ecs::entity::Entities, prelude::*, render::camera::ScalingMode};
+use bevy_rapier2d::prelude::*;
 use bevy_tweening::TweenCompleted;
 
-use self::player::Player;
+use self::{
+    obstacles::{slide_motion, CollisionMode, Solid, SolidBody},
+    player::Player,
+    wind::{Drift, Wind},
+};
 
 mod audio;
+mod biomes;
+mod companions;
 mod effects;
 mod enemies;
 mod environment;
 mod gui;
+mod hazards;
 mod items;
+mod obstacles;
 mod player;
+mod ports;
+mod radar;
+mod textures;
+mod weather;
+mod wind;
 
 pub struct CorePlugin;
 
@@ -23,6 +37,14 @@ impl Plugin for CorePlugin {
             enemies::EnemiesPlugin,
             effects::EffectsPlugin,
             audio::AudioManagerPlugin,
+            companions::CompanionsPlugin,
+            biomes::BiomesPlugin,
+            obstacles::ObstaclesPlugin,
+            wind::WindPlugin,
+            weather::WeatherPlugin,
+            ports::PortsPlugin,
+            radar::RadarPlugin,
+            hazards::HazardsPlugin,
         ))
         .insert_resource(IngameTime(0.))
         .insert_resource(GameStats::default())
@@ -79,6 +101,8 @@ enum GameState {
 enum PauseState {
     Running,
     Paused,
+    // At a port, the shop handles its own input
+    Docked,
 }
 
 #[derive(Component)]
@@ -130,14 +154,55 @@ fn update_ingame_time(time: Res<Time>, mut ingame_time: ResMut<IngameTime>) {
     ingame_time.0 += time.delta_seconds();
 }
 
-fn update_movement(time: Res<Time>, mut movement_query: Query<(&mut Movement, &mut Transform)>) {
-    for (mut movement, mut transform) in movement_query.iter_mut() {
+fn update_movement(
+    time: Res<Time>,
+    collision_mode: Res<CollisionMode>,
+    rapier_context: Res<RapierContext>,
+    wind: Res<Wind>,
+    mut movement_query: Query<(
+        &mut Movement,
+        &mut Transform,
+        Option<&SolidBody>,
+        Option<&Drift>,
+    )>,
+    solid_query: Query<&Transform, (With<Solid>, Without<Movement>)>,
+    mut carried_query: Query<(&mut Transform, &Drift), (Without<Movement>, Without<Solid>)>,
+) {
+    for (mut movement, mut transform, solid_body, drift) in movement_query.iter_mut() {
+        if let Some(drift) = drift {
+            let flow = wind.flow_at(transform.translation.xy());
+            movement.velocity += flow * drift.0 * time.delta_seconds();
+        }
+
         movement.velocity = movement
             .velocity
             .lerp(Vec2::ZERO, movement.friction * time.delta_seconds());
         movement.velocity = movement.velocity.clamp_length_max(movement.max_speed);
-        transform.translation.x += movement.velocity.x * time.delta_seconds();
-        transform.translation.y += movement.velocity.y * time.delta_seconds();
+
+        let mut motion = movement.velocity * time.delta_seconds();
+        if let (true, Some(solid_body)) = (collision_mode.solid, solid_body) {
+            motion = slide_motion(
+                &rapier_context,
+                &solid_query,
+                transform.translation.xy(),
+                solid_body.radius,
+                motion,
+            );
+            // Whatever pushed into the solid is lost
+            if time.delta_seconds() > 0. {
+                movement.velocity = motion / time.delta_seconds();
+            }
+        }
+
+        transform.translation.x += motion.x;
+        transform.translation.y += motion.y;
+    }
+
+    // Without a velocity of their own these are simply carried along
+    for (mut transform, drift) in carried_query.iter_mut() {
+        let flow = wind.flow_at(transform.translation.xy());
+        transform.translation.x += flow.x * drift.0 * time.delta_seconds();
+        transform.translation.y += flow.y * drift.0 * time.delta_seconds();
     }
 }
 
@@ -219,6 +284,7 @@ fn handle_pause(
         match pause_state.get() {
             PauseState::Paused => next_pause_state.set(PauseState::Running),
             PauseState::Running => next_pause_state.set(PauseState::Paused),
+            PauseState::Docked => {}
         }
     }
 }
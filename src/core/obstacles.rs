@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use super::{GameState, Movement};
+
+pub struct ObstaclesPlugin;
+
+impl Plugin for ObstaclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CollisionMode { solid: false })
+            .add_systems(
+                Update,
+                toggle_collision_mode.run_if(in_state(GameState::StartMenu)),
+            );
+    }
+}
+
+// Solid world geometry and damage sensors never see each other,
+// solids are only found by movement queries
+const SOLID_GROUP: Group = Group::GROUP_1;
+const SENSOR_GROUP: Group = Group::GROUP_2;
+
+// Motion is resolved against solids this many times per frame
+const SLIDE_ITERATIONS: usize = 3;
+// Gap kept between a body and the solid it stopped against
+const SKIN: f32 = 0.5;
+
+// Off keeps every obstacle a sensor that only bounces the ship
+#[derive(Resource)]
+pub struct CollisionMode {
+    pub solid: bool,
+}
+
+// Islands and large rocks, always a ball collider
+#[derive(Component)]
+pub struct Solid;
+
+// Slides along solids instead of passing through them
+#[derive(Component)]
+pub struct SolidBody {
+    pub radius: f32,
+}
+
+pub fn sensor_groups() -> CollisionGroups {
+    CollisionGroups::new(SENSOR_GROUP, SENSOR_GROUP)
+}
+
+pub fn solid_groups() -> CollisionGroups {
+    CollisionGroups::new(SOLID_GROUP, SOLID_GROUP)
+}
+
+pub fn hits_solid(rapier_context: &RapierContext, position: Vec2, radius: f32) -> bool {
+    rapier_context
+        .intersection_with_shape(
+            position,
+            0.,
+            &Collider::ball(radius),
+            QueryFilter::new().groups(solid_groups()),
+        )
+        .is_some()
+}
+
+// How far a body of this radius actually gets, sliding along any solid in the way
+pub fn slide_motion(
+    rapier_context: &RapierContext,
+    solid_query: &Query<&Transform, (With<Solid>, Without<Movement>)>,
+    position: Vec2,
+    radius: f32,
+    motion: Vec2,
+) -> Vec2 {
+    let shape = Collider::ball(radius);
+    let filter = QueryFilter::new().groups(solid_groups());
+
+    let mut current = position;
+    let mut remaining = motion;
+    for _ in 0..SLIDE_ITERATIONS {
+        let length = remaining.length();
+        if length <= f32::EPSILON {
+            break;
+        }
+
+        // Already overlapping shapes moving apart are ignored so nothing gets stuck inside
+        let Some((solid, hit)) =
+            rapier_context.cast_shape(current, 0., remaining, &shape, 1., false, filter)
+        else {
+            current += remaining;
+            break;
+        };
+
+        let travelled = (hit.toi - SKIN / length).max(0.);
+        current += remaining * travelled;
+
+        let Ok(solid_transform) = solid_query.get(solid) else {
+            break;
+        };
+        let normal = (current - solid_transform.translation.xy()).normalize_or_zero();
+
+        remaining *= 1. - travelled;
+        remaining -= normal * remaining.dot(normal).min(0.);
+    }
+
+    current - position
+}
+
+fn toggle_collision_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut collision_mode: ResMut<CollisionMode>,
+) {
+    if keys.just_pressed(KeyCode::KeyO) {
+        collision_mode.solid = !collision_mode.solid;
+    }
+}
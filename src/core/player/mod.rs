@@ -12,7 +12,9 @@ use crate::core::{GameState, PauseState};
 use super::TweenDespawn;
 use super::{
     enemies::{ContactEnemy, EnemyKnockback},
-    items::Inventory,
+    items::{Inventory, Item},
+    obstacles::{sensor_groups, SolidBody},
+    wind::{Drift, SailTrim},
     MainCamera, Movement,
 };
 
@@ -20,9 +22,11 @@ pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<XpGained>()
+        app.insert_resource(LostParts(Vec::new()))
+            .add_event::<XpGained>()
             .add_event::<LevelUp>()
             .add_event::<StatIncrease>()
+            .add_event::<PlayerHit>()
             .add_systems(Startup, setup_player)
             .add_systems(
                 Update,
@@ -37,7 +41,10 @@ impl Plugin for PlayerPlugin {
                     .run_if(in_state(PauseState::Running)),
             )
             .add_systems(OnEnter(GameState::GameOver), (handle_dead))
-            .add_systems(OnEnter(GameState::Game), (reset_player, show_tutorial));
+            .add_systems(
+                OnEnter(GameState::Game),
+                (reset_player, reset_lost_parts, show_tutorial),
+            );
     }
 }
 
@@ -47,9 +54,16 @@ pub struct XpGained(pub f32);
 #[derive(Event)]
 pub struct LevelUp(pub u32);
 
+#[derive(Event)]
+pub struct PlayerHit;
+
 #[derive(Event)]
 pub struct StatIncrease(pub String);
 
+// Parts knocked off by hits this run, most recent last
+#[derive(Resource)]
+pub struct LostParts(pub Vec<Item>);
+
 pub fn level_required_xp(level: u32) -> f32 {
     XP_SCALING * level as f32
 }
@@ -159,6 +173,7 @@ fn setup_player(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         Collider::capsule_x(44.0, 12.0),
         Sensor,
+        sensor_groups(),
         ActiveCollisionTypes::STATIC_STATIC,
         ActiveEvents::COLLISION_EVENTS,
         Player { acceleration: 300. },
@@ -180,6 +195,9 @@ fn setup_player(mut commands: Commands, asset_server: Res<AssetServer>) {
         },
         YSort(0.),
         Leveling::default(),
+        SolidBody { radius: 20. },
+        Drift(1.),
+        SailTrim::default(),
     ));
 }
 
@@ -198,6 +216,10 @@ fn reset_player(
     player_movement.velocity = Vec2::ZERO;
 }
 
+fn reset_lost_parts(mut lost_parts: ResMut<LostParts>) {
+    lost_parts.0.clear();
+}
+
 fn update_camera(
     mut camera_query: Query<&mut Transform, With<MainCamera>>,
     player_query: Query<&Transform, (With<Player>, Without<MainCamera>)>,
@@ -211,7 +233,7 @@ fn update_camera(
 
 fn handle_movement(
     mut commands: Commands,
-    mut player_query: Query<(&Player, &mut Movement, &mut Sprite)>,
+    mut player_query: Query<(&Player, &SailTrim, &mut Movement, &mut Sprite)>,
     keys: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
     tutorial_query: Query<Entity, With<Tutorial>>,
@@ -233,7 +255,8 @@ fn handle_movement(
 
     let normalized = direction.normalize_or_zero();
 
-    let (player, mut player_movement, mut player_sprite) = player_query.get_single_mut().unwrap();
+    let (player, sail_trim, mut player_movement, mut player_sprite) =
+        player_query.get_single_mut().unwrap();
 
     if normalized.x < 0. {
         player_sprite.flip_x = true;
@@ -259,7 +282,7 @@ fn handle_movement(
         }
     }
 
-    let acceleration = player.acceleration;
+    let acceleration = player.acceleration * sail_trim.multiplier;
     player_movement.velocity += acceleration * normalized * time.delta_seconds();
 }
 
@@ -282,8 +305,10 @@ fn handle_player_collisions(
     mut collision_events: EventReader<CollisionEvent>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut inventory: ResMut<Inventory>,
+    mut lost_parts: ResMut<LostParts>,
     mut player_query: Query<(&mut InvulnerabilityTimer, &mut Movement, &Transform), With<Player>>,
     enemies_query: Query<(&Transform, Option<&EnemyKnockback>), With<ContactEnemy>>,
+    mut ev_player_hit: EventWriter<PlayerHit>,
 ) {
     for collision_event in collision_events.read() {
         match collision_event {
@@ -308,11 +333,12 @@ fn handle_player_collisions(
 
                         player_movement.velocity += direction * enemy_knockback.knockback;
                     }
+                    ev_player_hit.send(PlayerHit);
                     if inventory.0.len() == 0 {
                         next_game_state.set(GameState::GameOver);
                     } else {
                         player_invuln.timer.reset();
-                        inventory.0.pop_front();
+                        lost_parts.0.extend(inventory.0.pop_front());
                     }
                 };
             }
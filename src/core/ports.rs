@@ -0,0 +1,558 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    thread_rng, Rng, SeedableRng,
+};
+use std::{collections::HashMap, f32::consts::PI};
+
+use super::{
+    enemies::{affixes::Elite, bosses::Boss, death::EnemyKilled},
+    environment::{chunk_rng, WorldSeed},
+    gui::ShowBanner,
+    items::{get_item_name, Inventory, Item, SlotTiers, INVENTORY_SIZE, MAX_TIER},
+    obstacles::{sensor_groups, solid_groups, Solid},
+    player::{LostParts, Player},
+    DistanceDespawn, GameDespawn, GameState, GameStats, IngameTime, PauseState, TimedDespawn,
+    YSort,
+};
+
+pub struct PortsPlugin;
+
+impl Plugin for PortsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Gold(0))
+            .insert_resource(PortStocks(HashMap::new()))
+            .insert_resource(ShopCursor(0))
+            .add_systems(
+                Update,
+                (drop_gold, handle_gold_pickups, dock_at_ports)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(
+                Update,
+                (handle_shop_input, update_shop_gui)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Docked)),
+            )
+            .add_systems(OnEnter(PauseState::Docked), setup_shop_gui)
+            .add_systems(OnExit(PauseState::Docked), cleanup_shop_gui)
+            .add_systems(OnEnter(GameState::Game), reset_ports);
+    }
+}
+
+const HARBOR_SCALE: f32 = 4.;
+const HARBOR_COLOR: Color = Color::rgb(0.75, 0.6, 0.4);
+// From the edge of the harbor island
+const DOCK_RANGE: f32 = 64.;
+
+const GOLD_CHANCE: f32 = 0.35;
+// Each affix adds this many coins
+const ELITE_GOLD: u32 = 2;
+const BOSS_GOLD: u32 = 25;
+const GOLD_SCATTER: f32 = 16.;
+const GOLD_LIFETIME: f32 = 60.;
+const GOLD_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+
+const STOCK_SIZE: usize = 3;
+// Prices and the chance of rarer parts go up by one base amount every this many seconds
+const STOCK_SCALING_TIME: f32 = 300.;
+const PRICE_JITTER: f32 = 0.2;
+const UPGRADE_PRICE: f32 = 40.;
+const REPAIR_PRICE: f32 = 25.;
+const REROLL_PRICE: f32 = 10.;
+
+// Base weight, extra weight per scaling period and base price
+const STOCK_TABLE: [(Item, f32, f32, f32); 5] = [
+    (Item::Spear, 4., 0., 20.),
+    (Item::Bow, 4., 0., 20.),
+    (Item::GreekFire, 3., 1., 30.),
+    (Item::PoseidonTrident, 1., 2., 45.),
+    (Item::ZeusThunderbolt, 1., 2., 45.),
+];
+
+const PORT_NAMES: [&str; 8] = [
+    "Piraeus", "Ithaca", "Rhodes", "Delos", "Knossos", "Syracuse", "Miletus", "Corinth",
+];
+
+const SHOP_FONT_SIZE: f32 = 28.;
+const SELECTED_COLOR: Color = Color::rgb(0.6, 0.3, 0.);
+const UNAFFORDABLE_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
+
+// Dropped by enemies, spent at ports
+#[derive(Resource)]
+pub struct Gold(pub u32);
+
+#[derive(Component)]
+struct GoldPickup(u32);
+
+#[derive(Component)]
+pub struct Port {
+    chunk: IVec2,
+}
+
+// Each port keeps its stock between visits
+struct PortStock {
+    name: &'static str,
+    // Sold parts are taken off the shelf
+    parts: Vec<Option<(Item, u32)>>,
+    price_scale: f32,
+    rerolls: u32,
+}
+
+#[derive(Resource)]
+struct PortStocks(HashMap<IVec2, PortStock>);
+
+// The port the player is docked at
+#[derive(Resource)]
+struct Docked(IVec2);
+
+#[derive(Resource)]
+struct ShopCursor(usize);
+
+#[derive(Clone, Copy)]
+enum ShopOffer {
+    Part(usize),
+    Upgrade(usize),
+    Repair,
+    Reroll,
+    CastOff,
+}
+
+#[derive(Component)]
+struct ShopMenu;
+
+#[derive(Component)]
+struct ShopText;
+
+fn roll_stock(world_seed: &WorldSeed, chunk: IVec2, rerolls: u32, ingame_time: f32) -> PortStock {
+    let port_seed: u64 = chunk_rng(world_seed, chunk).gen();
+    let name = PORT_NAMES[(port_seed % PORT_NAMES.len() as u64) as usize];
+    let mut rng = StdRng::seed_from_u64(port_seed.wrapping_add(rerolls as u64));
+
+    let periods = ingame_time / STOCK_SCALING_TIME;
+    let price_scale = 1. + periods;
+    let weights = WeightedIndex::new(
+        STOCK_TABLE
+            .iter()
+            .map(|(_, base, growth, _)| base + growth * periods),
+    )
+    .unwrap();
+
+    let parts = (0..STOCK_SIZE)
+        .map(|_| {
+            let (item, _, _, price) = STOCK_TABLE[weights.sample(&mut rng)];
+            let jitter = rng.gen_range((1. - PRICE_JITTER)..(1. + PRICE_JITTER));
+            Some((item, (price * price_scale * jitter).round() as u32))
+        })
+        .collect();
+
+    PortStock {
+        name,
+        parts,
+        price_scale,
+        rerolls,
+    }
+}
+
+fn upgrade_price(stock: &PortStock, tier: u32) -> u32 {
+    (UPGRADE_PRICE * (tier + 1) as f32 * stock.price_scale).round() as u32
+}
+
+fn reroll_price(stock: &PortStock) -> u32 {
+    (REROLL_PRICE * (stock.rerolls + 1) as f32 * stock.price_scale).round() as u32
+}
+
+// Everything the shop can do right now, with a label and a price
+fn shop_offers(
+    stock: &PortStock,
+    inventory: &Inventory,
+    slot_tiers: &SlotTiers,
+    lost_parts: &LostParts,
+) -> Vec<(ShopOffer, String, u32)> {
+    let mut offers = Vec::new();
+
+    for (index, part) in stock.parts.iter().enumerate() {
+        if let Some((item, price)) = part {
+            offers.push((ShopOffer::Part(index), get_item_name(item).into(), *price));
+        }
+    }
+    for (slot, item) in inventory.0.iter().enumerate() {
+        let tier = slot_tiers.0[slot];
+        if tier >= MAX_TIER {
+            continue;
+        }
+        offers.push((
+            ShopOffer::Upgrade(slot),
+            format!(
+                "Reinforce slot {} ({}) to tier {}",
+                slot + 1,
+                get_item_name(item),
+                tier + 1
+            ),
+            upgrade_price(stock, tier),
+        ));
+    }
+    if let Some(part) = lost_parts.0.last() {
+        if inventory.0.len() < INVENTORY_SIZE {
+            offers.push((
+                ShopOffer::Repair,
+                format!("Repair {}", get_item_name(part)),
+                (REPAIR_PRICE * stock.price_scale).round() as u32,
+            ));
+        }
+    }
+    offers.push((ShopOffer::Reroll, "Restock".into(), reroll_price(stock)));
+    offers.push((ShopOffer::CastOff, "Cast off".into(), 0));
+
+    offers
+}
+
+// Spawned in either collision mode, but only blocks the ship when obstacles are solid
+pub fn spawn_harbor(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    chunk: IVec2,
+    spawn_location: Vec2,
+    solid: bool,
+) -> Entity {
+    let harbor = commands
+        .spawn((
+            Port { chunk },
+            SpriteBundle {
+                texture: asset_server.load("sprites/obstacles/rock.png"),
+                transform: Transform {
+                    translation: spawn_location.extend(0.),
+                    scale: Vec3::splat(HARBOR_SCALE),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: HARBOR_COLOR,
+                    ..default()
+                },
+                ..default()
+            },
+            YSort(0.),
+            GameDespawn,
+        ))
+        .id();
+
+    if solid {
+        commands.entity(harbor).insert((
+            Solid,
+            Collider::ball(16.),
+            RigidBody::Fixed,
+            solid_groups(),
+        ));
+    }
+
+    harbor
+}
+
+fn drop_gold(
+    mut commands: Commands,
+    mut ev_enemy_killed: EventReader<EnemyKilled>,
+    dead_query: Query<(Option<&Elite>, Option<&Boss>)>,
+) {
+    let mut rng = thread_rng();
+
+    for event in ev_enemy_killed.read() {
+        let Ok((elite, boss)) = dead_query.get(event.entity) else {
+            continue;
+        };
+
+        let mut amount = 0;
+        if rng.gen_bool(GOLD_CHANCE.into()) {
+            amount += 1;
+        }
+        if let Some(elite) = elite {
+            amount += ELITE_GOLD * elite.affixes.len() as u32;
+        }
+        if boss.is_some() {
+            amount += BOSS_GOLD;
+        }
+        if amount == 0 {
+            continue;
+        }
+
+        let position = event.position
+            + Vec2::from_angle(rng.gen_range((0.)..(2. * PI))) * rng.gen_range((0.)..GOLD_SCATTER);
+
+        commands.spawn((
+            GoldPickup(amount),
+            Collider::ball(16.),
+            Sensor,
+            sensor_groups(),
+            ActiveEvents::COLLISION_EVENTS,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: GOLD_COLOR,
+                    custom_size: Some(Vec2::splat(8. + amount.min(8) as f32)),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: position.extend(0.),
+                    rotation: Quat::from_rotation_z(PI / 4.),
+                    ..default()
+                },
+                ..default()
+            },
+            YSort(0.),
+            TimedDespawn {
+                delay: GOLD_LIFETIME,
+            },
+            DistanceDespawn,
+            GameDespawn,
+        ));
+    }
+}
+
+fn handle_gold_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut gold: ResMut<Gold>,
+    gold_query: Query<(&GoldPickup, Entity)>,
+    player_query: Query<&Player>,
+) {
+    for collision_event in collision_events.read() {
+        let CollisionEvent::Started(entity_one, entity_two, _) = collision_event else {
+            continue;
+        };
+        let entities = [entity_one, entity_two];
+        let mut maybe_gold = gold_query.iter_many(entities);
+        let mut maybe_player = player_query.iter_many(entities);
+
+        let (Some((gold_pickup, gold_entity)), Some(_)) =
+            (maybe_gold.fetch_next(), maybe_player.fetch_next())
+        else {
+            continue;
+        };
+
+        commands.entity(gold_entity).despawn_recursive();
+        gold.0 += gold_pickup.0;
+    }
+}
+
+fn dock_at_ports(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    world_seed: Res<WorldSeed>,
+    ingame_time: Res<IngameTime>,
+    mut port_stocks: ResMut<PortStocks>,
+    mut shop_cursor: ResMut<ShopCursor>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+    mut in_range: Local<Option<Entity>>,
+    player_query: Query<&Transform, With<Player>>,
+    port_query: Query<(Entity, &Port, &Transform), Without<Player>>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.xy();
+
+    let nearby = port_query.iter().find(|(_, _, transform)| {
+        transform.translation.xy().distance(player_pos) < 16. * HARBOR_SCALE + DOCK_RANGE
+    });
+    let Some((port_entity, port, _)) = nearby else {
+        *in_range = None;
+        return;
+    };
+
+    if *in_range != Some(port_entity) {
+        *in_range = Some(port_entity);
+        ev_show_banner.send(ShowBanner("Press E to dock".into()));
+    }
+
+    if keys.just_pressed(KeyCode::KeyE) {
+        port_stocks
+            .0
+            .entry(port.chunk)
+            .or_insert_with(|| roll_stock(&world_seed, port.chunk, 0, ingame_time.0));
+        commands.insert_resource(Docked(port.chunk));
+        shop_cursor.0 = 0;
+        next_pause_state.set(PauseState::Docked);
+    }
+}
+
+fn handle_shop_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    world_seed: Res<WorldSeed>,
+    ingame_time: Res<IngameTime>,
+    docked: Res<Docked>,
+    mut gold: ResMut<Gold>,
+    mut port_stocks: ResMut<PortStocks>,
+    mut shop_cursor: ResMut<ShopCursor>,
+    mut inventory: ResMut<Inventory>,
+    mut slot_tiers: ResMut<SlotTiers>,
+    mut lost_parts: ResMut<LostParts>,
+    mut game_stats: ResMut<GameStats>,
+    mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_pause_state.set(PauseState::Running);
+        return;
+    }
+
+    let Some(stock) = port_stocks.0.get_mut(&docked.0) else {
+        return;
+    };
+    let offers = shop_offers(stock, &inventory, &slot_tiers, &lost_parts);
+
+    if keys.just_pressed(KeyCode::ArrowUp) || keys.just_pressed(KeyCode::KeyW) {
+        shop_cursor.0 = (shop_cursor.0 + offers.len() - 1) % offers.len();
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) || keys.just_pressed(KeyCode::KeyS) {
+        shop_cursor.0 = (shop_cursor.0 + 1) % offers.len();
+    }
+    // The list shrinks as parts are sold
+    shop_cursor.0 = shop_cursor.0.min(offers.len() - 1);
+
+    if !keys.just_pressed(KeyCode::Space) && !keys.just_pressed(KeyCode::Enter) {
+        return;
+    }
+
+    let (offer, _, price) = offers[shop_cursor.0];
+    if gold.0 < price {
+        return;
+    }
+    gold.0 -= price;
+
+    match offer {
+        ShopOffer::Part(index) => {
+            let Some((item, _)) = stock.parts[index].take() else {
+                return;
+            };
+            inventory.0.push_back(item);
+            if inventory.0.len() > INVENTORY_SIZE {
+                inventory.0.pop_front();
+            }
+            game_stats.items_collected += 1;
+        }
+        ShopOffer::Upgrade(slot) => {
+            slot_tiers.0[slot] += 1;
+        }
+        ShopOffer::Repair => {
+            inventory.0.extend(lost_parts.0.pop());
+        }
+        ShopOffer::Reroll => {
+            *stock = roll_stock(&world_seed, docked.0, stock.rerolls + 1, ingame_time.0);
+        }
+        ShopOffer::CastOff => {
+            next_pause_state.set(PauseState::Running);
+        }
+    }
+}
+
+fn setup_shop_gui(mut commands: Commands) {
+    commands
+        .spawn((
+            ShopMenu,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    background_color: Color::rgba(1., 1., 1., 0.75).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        ShopText,
+                        TextBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(20.)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn update_shop_gui(
+    asset_server: Res<AssetServer>,
+    docked: Res<Docked>,
+    gold: Res<Gold>,
+    port_stocks: Res<PortStocks>,
+    shop_cursor: Res<ShopCursor>,
+    inventory: Res<Inventory>,
+    slot_tiers: Res<SlotTiers>,
+    lost_parts: Res<LostParts>,
+    mut shop_text_query: Query<&mut Text, With<ShopText>>,
+) {
+    let Ok(mut shop_text) = shop_text_query.get_single_mut() else {
+        return;
+    };
+    let Some(stock) = port_stocks.0.get(&docked.0) else {
+        return;
+    };
+
+    let style = |font_size: f32, color: Color| TextStyle {
+        font: asset_server.load("fonts/pixel_font.ttf"),
+        font_size,
+        color,
+    };
+
+    let mut sections = vec![
+        TextSection::new(format!("{}\n", stock.name), style(42., Color::BLACK)),
+        TextSection::new(
+            format!("{} gold\n\n", gold.0),
+            style(SHOP_FONT_SIZE, Color::BLACK),
+        ),
+    ];
+    for (index, (_, label, price)) in shop_offers(stock, &inventory, &slot_tiers, &lost_parts)
+        .into_iter()
+        .enumerate()
+    {
+        let selected = index == shop_cursor.0;
+        let color = if gold.0 < price {
+            UNAFFORDABLE_COLOR
+        } else if selected {
+            SELECTED_COLOR
+        } else {
+            Color::BLACK
+        };
+        let cursor = if selected { "> " } else { "  " };
+        let price = if price > 0 {
+            format!("  {}g", price)
+        } else {
+            String::new()
+        };
+        sections.push(TextSection::new(
+            format!("{}{}{}\n", cursor, label, price),
+            style(SHOP_FONT_SIZE, color),
+        ));
+    }
+    sections.push(TextSection::new(
+        "\nUp/Down to choose, Space to buy, Esc to cast off",
+        style(20., Color::BLACK),
+    ));
+
+    shop_text.sections = sections;
+}
+
+fn cleanup_shop_gui(mut commands: Commands, shop_menu_query: Query<Entity, With<ShopMenu>>) {
+    for shop_menu in shop_menu_query.iter() {
+        commands.entity(shop_menu).despawn_recursive();
+    }
+}
+
+fn reset_ports(mut gold: ResMut<Gold>, mut port_stocks: ResMut<PortStocks>) {
+    gold.0 = 0;
+    port_stocks.0.clear();
+}
@@ -0,0 +1,319 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use std::f32::consts::FRAC_PI_2;
+
+use super::{
+    enemies::{
+        affixes::Elite, bosses::Boss, defs::EnemyDefs, loot::LootPickup, Enemy, EnemyArchetype,
+    },
+    environment::ItemPickup,
+    hazards::{Charybdis, Whirlpool},
+    player::Player,
+    ports::Port,
+    GameState, Movement, PauseState,
+};
+
+pub struct RadarPlugin;
+
+impl Plugin for RadarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Radar>()
+            .add_systems(OnEnter(GameState::Game), setup_radar_gui)
+            .add_systems(OnExit(GameState::Game), cleanup_radar_gui)
+            .add_systems(
+                Update,
+                (handle_radar_keys, update_heading, draw_radar)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            );
+    }
+}
+
+// Pixels across the radar texture, drawn at twice the size on screen
+const RADAR_SIZE: u32 = 96;
+const RADAR_SCALE: f32 = 2.;
+const RADAR_MARGIN: f32 = 24.;
+// World units from the center to the edge at each zoom level
+const ZOOM_LEVELS: [f32; 3] = [800., 1600., 3200.];
+// Redrawn at most this often, each redraw uploads the whole texture
+const REDRAW_INTERVAL: f32 = 0.05;
+// Below this speed the heading is held
+const HEADING_MIN_SPEED: f32 = 20.;
+// Fraction of the turn made per second
+const HEADING_TURN_RATE: f32 = 4.;
+
+const BACKGROUND_COLOR: [u8; 4] = [10, 20, 40, 160];
+const RING_COLOR: [u8; 4] = [120, 180, 220, 200];
+const PLAYER_COLOR: [u8; 4] = [255, 255, 255, 255];
+const ELITE_COLOR: [u8; 4] = [255, 215, 60, 255];
+const BOSS_COLOR: [u8; 4] = [255, 40, 40, 255];
+const ITEM_COLOR: [u8; 4] = [120, 255, 140, 255];
+const LOOT_COLOR: [u8; 4] = [200, 255, 200, 255];
+const PORT_COLOR: [u8; 4] = [255, 190, 100, 255];
+const ENEMY_COLOR: [u8; 4] = [255, 120, 120, 255];
+const WHIRLPOOL_COLOR: [u8; 4] = [160, 220, 255, 255];
+
+#[derive(Resource)]
+struct Radar {
+    visible: bool,
+    zoom: usize,
+    // Radians the view is turned by, so the ship always points up
+    heading: f32,
+    since_redraw: f32,
+    image: Handle<Image>,
+}
+
+impl FromWorld for Radar {
+    fn from_world(world: &mut World) -> Self {
+        let image = Image::new_fill(
+            Extent3d {
+                width: RADAR_SIZE,
+                height: RADAR_SIZE,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        );
+
+        Radar {
+            visible: true,
+            zoom: 0,
+            heading: 0.,
+            since_redraw: REDRAW_INTERVAL,
+            image: world.resource_mut::<Assets<Image>>().add(image),
+        }
+    }
+}
+
+#[derive(Component)]
+struct RadarGui;
+
+fn enemy_color(enemy_defs: &EnemyDefs, archetype: &EnemyArchetype) -> [u8; 4] {
+    let colors = [
+        (&enemy_defs.ferris, [255, 140, 60, 255]),
+        (&enemy_defs.serpent, [90, 220, 120, 255]),
+        (&enemy_defs.elite_serpent, [40, 160, 80, 255]),
+        (&enemy_defs.siren, [255, 130, 220, 255]),
+        (&enemy_defs.hydra, [180, 110, 255, 255]),
+        (&enemy_defs.wyvern, [255, 90, 60, 255]),
+        (&enemy_defs.kraken, [80, 140, 255, 255]),
+    ];
+
+    colors
+        .iter()
+        .find(|(handle, _)| **handle == archetype.0)
+        .map_or(ENEMY_COLOR, |(_, color)| *color)
+}
+
+// Raw pixel writes, y grows downwards
+fn plot(data: &mut [u8], x: i32, y: i32, size: i32, color: [u8; 4]) {
+    let half = size / 2;
+    for py in (y - half)..(y - half + size) {
+        for px in (x - half)..(x - half + size) {
+            if px < 0 || py < 0 || px >= RADAR_SIZE as i32 || py >= RADAR_SIZE as i32 {
+                continue;
+            }
+            let index = ((py as u32 * RADAR_SIZE + px as u32) * 4) as usize;
+            data[index..index + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+fn setup_radar_gui(mut commands: Commands, radar: Res<Radar>) {
+    let size = Val::Px(RADAR_SIZE as f32 * RADAR_SCALE);
+
+    commands.spawn((
+        RadarGui,
+        ImageBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(RADAR_MARGIN),
+                bottom: Val::Px(RADAR_MARGIN),
+                width: size,
+                height: size,
+                ..default()
+            },
+            image: UiImage::new(radar.image.clone()),
+            visibility: if radar.visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            },
+            ..default()
+        },
+    ));
+}
+
+fn cleanup_radar_gui(mut commands: Commands, radar_gui_query: Query<Entity, With<RadarGui>>) {
+    for radar_gui in radar_gui_query.iter() {
+        commands.entity(radar_gui).despawn_recursive();
+    }
+}
+
+// M shows or hides the radar, Z cycles the zoom
+fn handle_radar_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut radar: ResMut<Radar>,
+    mut radar_gui_query: Query<&mut Visibility, With<RadarGui>>,
+) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        radar.visible = !radar.visible;
+        for mut visibility in radar_gui_query.iter_mut() {
+            *visibility = if radar.visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+    if keys.just_pressed(KeyCode::KeyZ) {
+        radar.zoom = (radar.zoom + 1) % ZOOM_LEVELS.len();
+        radar.since_redraw = REDRAW_INTERVAL;
+    }
+}
+
+fn update_heading(
+    time: Res<Time>,
+    mut radar: ResMut<Radar>,
+    player_query: Query<&Movement, With<Player>>,
+) {
+    let Ok(movement) = player_query.get_single() else {
+        return;
+    };
+    if movement.velocity.length() < HEADING_MIN_SPEED {
+        return;
+    }
+
+    // Turn the short way round
+    let target = movement.velocity.to_angle();
+    let difference = Vec2::from_angle(target - radar.heading).to_angle();
+    radar.heading += difference * (HEADING_TURN_RATE * time.delta_seconds()).min(1.);
+}
+
+fn draw_radar(
+    time: Res<Time>,
+    mut radar: ResMut<Radar>,
+    mut images: ResMut<Assets<Image>>,
+    enemy_defs: Res<EnemyDefs>,
+    player_query: Query<&Transform, With<Player>>,
+    enemies_query: Query<
+        (&Transform, Option<&EnemyArchetype>, Option<&Elite>),
+        (With<Enemy>, Without<Boss>),
+    >,
+    boss_query: Query<&Transform, With<Boss>>,
+    item_query: Query<&Transform, With<ItemPickup>>,
+    loot_query: Query<&Transform, With<LootPickup>>,
+    port_query: Query<&Transform, With<Port>>,
+    whirlpool_query: Query<(&Transform, Has<Charybdis>), With<Whirlpool>>,
+) {
+    radar.since_redraw += time.delta_seconds();
+    if !radar.visible || radar.since_redraw < REDRAW_INTERVAL {
+        return;
+    }
+    radar.since_redraw = 0.;
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let Some(image) = images.get_mut(&radar.image) else {
+        return;
+    };
+
+    let player_pos = player_transform.translation.xy();
+    let center = RADAR_SIZE as f32 / 2.;
+    let range = ZOOM_LEVELS[radar.zoom];
+    // Turns the heading to face up
+    let rotation = Vec2::from_angle(FRAC_PI_2 - radar.heading);
+    let pixels_per_unit = center / range;
+
+    // Radar pixel for a world position, or None when it's out of range and not pinned to the edge
+    let to_pixel = |position: Vec2, pinned: bool| -> Option<(i32, i32)> {
+        let mut offset = rotation.rotate(position - player_pos) * pixels_per_unit;
+        if offset.length() > center - 1. {
+            if !pinned {
+                return None;
+            }
+            offset = offset.clamp_length_max(center - 2.);
+        }
+        Some(((center + offset.x) as i32, (center - offset.y) as i32))
+    };
+
+    let data = &mut image.data;
+    for y in 0..RADAR_SIZE {
+        for x in 0..RADAR_SIZE {
+            let distance = Vec2::new(x as f32 + 0.5 - center, y as f32 + 0.5 - center).length();
+            let color = if distance > center {
+                [0, 0, 0, 0]
+            } else if distance > center - 1. || (distance - center / 2.).abs() < 0.5 {
+                RING_COLOR
+            } else {
+                BACKGROUND_COLOR
+            };
+            let index = ((y * RADAR_SIZE + x) * 4) as usize;
+            data[index..index + 4].copy_from_slice(&color);
+        }
+    }
+
+    // North stays marked on the rim
+    if let Some((x, y)) = to_pixel(player_pos + Vec2::Y * range * 2., true) {
+        plot(data, x, y, 2, RING_COLOR);
+    }
+
+    for transform in item_query.iter() {
+        if let Some((x, y)) = to_pixel(transform.translation.xy(), false) {
+            plot(data, x, y, 2, ITEM_COLOR);
+        }
+    }
+    for (transform, is_charybdis) in whirlpool_query.iter() {
+        if let Some((x, y)) = to_pixel(transform.translation.xy(), is_charybdis) {
+            plot(
+                data,
+                x,
+                y,
+                if is_charybdis { 5 } else { 3 },
+                WHIRLPOOL_COLOR,
+            );
+        }
+    }
+    for transform in loot_query.iter() {
+        if let Some((x, y)) = to_pixel(transform.translation.xy(), false) {
+            plot(data, x, y, 1, LOOT_COLOR);
+        }
+    }
+    for (transform, archetype, elite) in enemies_query.iter() {
+        let Some((x, y)) = to_pixel(transform.translation.xy(), false) else {
+            continue;
+        };
+        let color = archetype.map_or(ENEMY_COLOR, |archetype| enemy_color(&enemy_defs, archetype));
+        // Elites get a gold rim around their type color
+        if elite.is_some() {
+            plot(data, x, y, 4, ELITE_COLOR);
+        }
+        plot(data, x, y, 2, color);
+    }
+    // Points of interest and bosses stay on the rim when out of range
+    for transform in port_query.iter() {
+        if let Some((x, y)) = to_pixel(transform.translation.xy(), true) {
+            plot(data, x, y, 4, PORT_COLOR);
+        }
+    }
+    for transform in boss_query.iter() {
+        if let Some((x, y)) = to_pixel(transform.translation.xy(), true) {
+            plot(data, x, y, 6, PLAYER_COLOR);
+            plot(data, x, y, 4, BOSS_COLOR);
+        }
+    }
+
+    // The ship, pointing up
+    let middle = center as i32;
+    plot(data, middle, middle - 2, 1, PLAYER_COLOR);
+    plot(data, middle, middle, 3, PLAYER_COLOR);
+}
@@ -0,0 +1,32 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+// A white square texture, each pixel's alpha from its offset from the center in -1..1
+// and its distance from the center, 1 at the middle of each edge
+pub fn radial_texture(size: u32, alpha: impl Fn(Vec2, f32) -> f32) -> Image {
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let offset = (Vec2::new(x as f32, y as f32) + 0.5) / size as f32 * 2. - 1.;
+            let alpha = alpha(offset, offset.length()).clamp(0., 1.);
+            data.extend([255, 255, 255, (alpha * 255.) as u8]);
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
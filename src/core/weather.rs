@@ -0,0 +1,429 @@
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_rapier2d::prelude::*;
+use bevy_tweening::{lens::SpriteColorLens, Animator, EaseMethod, Tween};
+use rand::{distributions::WeightedIndex, prelude::Distribution, thread_rng, Rng};
+use std::{f32::consts::PI, time::Duration};
+
+use super::{
+    biomes::{Biome, CurrentBiome},
+    enemies::{ContactEnemy, DamageEvent, DamageKind, Enemy, EnemyKnockback},
+    gui::ShowBanner,
+    items::ATTACK_RANGE,
+    obstacles::sensor_groups,
+    player::Player,
+    textures::radial_texture,
+    GameDespawn, GameState, IngameTime, PauseState, TimedDespawn, TweenDespawn, YSort,
+};
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Weather::default())
+            .add_systems(Startup, setup_overlays)
+            .add_systems(
+                Update,
+                (
+                    update_weather,
+                    update_overlays,
+                    spawn_lightning,
+                    strike_lightning,
+                )
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(GameState::Game), (reset_weather, reset_overlays));
+
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, force_weather.run_if(in_state(GameState::Game)));
+    }
+}
+
+// Seconds for one full day and night
+const DAY_LENGTH: f32 = 240.;
+const NIGHT_DARKNESS: f32 = 0.8;
+// Storm clouds darken the day too
+const STORM_DARKNESS: f32 = 0.45;
+const LIGHT_RADIUS: f32 = 260.;
+
+// Weather holds for a random stretch of this many seconds
+const WEATHER_DURATION: (f32, f32) = (45., 90.);
+// The first stretch of every run is always clear
+const FIRST_CHANGE: f32 = 60.;
+// How much more often storms brew over the Storm Sea
+const STORM_SEA_WEIGHT: i32 = 3;
+
+const FOG_DENSITY: f32 = 0.85;
+const FOG_COLOR: Color = Color::rgb(0.8, 0.82, 0.85);
+// Share of the attack range still visible through fog
+const FOG_VISIBILITY: f32 = 0.6;
+const CALM_SPAWN_MULTIPLIER: f32 = 0.5;
+const CALM_WIND_MULTIPLIER: f32 = 0.3;
+const STORM_WIND_MULTIPLIER: f32 = 1.75;
+// Overlays reach their target alpha over this many seconds
+const OVERLAY_FADE_TIME: f32 = 3.;
+// Drawn over the world, under damage numbers
+const OVERLAY_Z: f32 = 2.;
+
+// Normalised radius where the overlay texture starts and stops fading in
+const LIGHT_INNER: f32 = 0.1;
+const LIGHT_OUTER: f32 = 0.2;
+const LIGHT_TEXTURE_SIZE: u32 = 256;
+
+const STRIKE_INTERVAL: (f32, f32) = (1.5, 4.);
+const STRIKE_SPREAD: Vec2 = Vec2::new(600., 350.);
+// Warning before the bolt lands
+const STRIKE_DELAY: f32 = 1.;
+const STRIKE_RADIUS: f32 = 60.;
+const STRIKE_DAMAGE: f32 = 150.;
+const STRIKE_KNOCKBACK: f32 = 400.;
+// The bolt hurts the player for this long after landing
+const STRIKE_IMPACT_DURATION: f32 = 0.2;
+const STRIKE_MARKER_COLOR: Color = Color::rgba(1., 1., 0.4, 0.3);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WeatherState {
+    Clear,
+    Fog,
+    Storm,
+    Calm,
+}
+
+impl WeatherState {
+    fn name(&self) -> &'static str {
+        match self {
+            WeatherState::Clear => "The skies clear",
+            WeatherState::Fog => "Fog rolls in",
+            WeatherState::Storm => "A storm breaks",
+            WeatherState::Calm => "The sea falls calm",
+        }
+    }
+
+    fn weight(&self, biome: Biome) -> i32 {
+        match (self, biome) {
+            (WeatherState::Clear, _) => 4,
+            (WeatherState::Storm, Biome::StormSea) => 2 * STORM_SEA_WEIGHT,
+            _ => 2,
+        }
+    }
+}
+
+const WEATHER_STATES: [WeatherState; 4] = [
+    WeatherState::Clear,
+    WeatherState::Fog,
+    WeatherState::Storm,
+    WeatherState::Calm,
+];
+
+#[derive(Resource)]
+pub struct Weather {
+    pub state: WeatherState,
+    // Ingame time of the next change
+    next_change: f32,
+    next_strike: f32,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Weather {
+            state: WeatherState::Clear,
+            next_change: FIRST_CHANGE,
+            next_strike: 0.,
+        }
+    }
+}
+
+impl Weather {
+    // Scales how far away weapons can pick out a target
+    pub fn visibility(&self) -> f32 {
+        match self.state {
+            WeatherState::Fog => FOG_VISIBILITY,
+            _ => 1.,
+        }
+    }
+
+    pub fn spawn_multiplier(&self) -> f32 {
+        match self.state {
+            WeatherState::Calm => CALM_SPAWN_MULTIPLIER,
+            _ => 1.,
+        }
+    }
+
+    pub fn wind_multiplier(&self) -> f32 {
+        match self.state {
+            WeatherState::Calm => CALM_WIND_MULTIPLIER,
+            WeatherState::Storm => STORM_WIND_MULTIPLIER,
+            _ => 1.,
+        }
+    }
+
+    fn set(&mut self, state: WeatherState, now: f32) {
+        self.state = state;
+        self.next_change = now + thread_rng().gen_range(WEATHER_DURATION.0..WEATHER_DURATION.1);
+        self.next_strike = now + STRIKE_INTERVAL.0;
+    }
+}
+
+// 0 through the day, 1 at midnight, every run starts at noon
+pub fn night_amount(ingame_time: f32) -> f32 {
+    let phase = ingame_time / DAY_LENGTH * 2. * PI;
+    (-phase.cos()).max(0.)
+}
+
+#[derive(Component)]
+enum Overlay {
+    Night,
+    Fog,
+}
+
+// Telegraphs where the bolt will land
+#[derive(Component)]
+struct LightningStrike {
+    lands_at: f32,
+}
+
+// Opaque with a soft transparent hole in the middle
+fn light_texture() -> Image {
+    radial_texture(LIGHT_TEXTURE_SIZE, |_, distance| {
+        let t = ((distance - LIGHT_INNER) / (LIGHT_OUTER - LIGHT_INNER)).clamp(0., 1.);
+        t * t * (3. - 2. * t)
+    })
+}
+
+// Sized so the hole has the given radius in world units
+fn overlay_size(radius: f32) -> Vec2 {
+    Vec2::splat(radius / LIGHT_OUTER * 2.)
+}
+
+fn setup_overlays(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let texture = images.add(light_texture());
+
+    for (overlay, color, radius) in [
+        (Overlay::Night, Color::BLACK, LIGHT_RADIUS),
+        (Overlay::Fog, FOG_COLOR, ATTACK_RANGE * FOG_VISIBILITY),
+    ] {
+        commands.spawn((
+            overlay,
+            SpriteBundle {
+                texture: texture.clone(),
+                sprite: Sprite {
+                    color: color.with_a(0.),
+                    custom_size: Some(overlay_size(radius)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0., 0., OVERLAY_Z),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn update_weather(
+    ingame_time: Res<IngameTime>,
+    current_biome: Res<CurrentBiome>,
+    mut weather: ResMut<Weather>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    if ingame_time.0 < weather.next_change {
+        return;
+    }
+
+    let choices = WEATHER_STATES
+        .iter()
+        .filter(|state| **state != weather.state)
+        .collect::<Vec<_>>();
+    let weights = WeightedIndex::new(
+        choices
+            .iter()
+            .map(|state| state.weight(current_biome.0))
+            .collect::<Vec<i32>>(),
+    )
+    .unwrap();
+    let state = *choices[weights.sample(&mut thread_rng())];
+
+    weather.set(state, ingame_time.0);
+    ev_show_banner.send(ShowBanner(state.name().into()));
+}
+
+fn update_overlays(
+    time: Res<Time>,
+    ingame_time: Res<IngameTime>,
+    weather: Res<Weather>,
+    player_query: Query<&Transform, With<Player>>,
+    mut overlay_query: Query<(&Overlay, &mut Sprite, &mut Transform), Without<Player>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let storm = if weather.state == WeatherState::Storm {
+        STORM_DARKNESS
+    } else {
+        0.
+    };
+    let fog = if weather.state == WeatherState::Fog {
+        FOG_DENSITY
+    } else {
+        0.
+    };
+    let night = NIGHT_DARKNESS * night_amount(ingame_time.0);
+    let step = time.delta_seconds() / OVERLAY_FADE_TIME;
+
+    for (overlay, mut sprite, mut transform) in overlay_query.iter_mut() {
+        transform.translation.x = player_transform.translation.x;
+        transform.translation.y = player_transform.translation.y;
+
+        let target = match overlay {
+            Overlay::Night => night.max(storm),
+            Overlay::Fog => fog,
+        };
+        let alpha = sprite.color.a();
+        sprite
+            .color
+            .set_a(alpha + (target - alpha).clamp(-step, step));
+    }
+}
+
+fn spawn_lightning(
+    mut commands: Commands,
+    ingame_time: Res<IngameTime>,
+    mut weather: ResMut<Weather>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    if weather.state != WeatherState::Storm || ingame_time.0 < weather.next_strike {
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let mut rng = thread_rng();
+    weather.next_strike = ingame_time.0 + rng.gen_range(STRIKE_INTERVAL.0..STRIKE_INTERVAL.1);
+
+    let position = player_transform.translation.xy()
+        + Vec2::new(
+            rng.gen_range(-STRIKE_SPREAD.x..STRIKE_SPREAD.x),
+            rng.gen_range(-STRIKE_SPREAD.y..STRIKE_SPREAD.y),
+        );
+
+    commands.spawn((
+        LightningStrike {
+            lands_at: ingame_time.0 + STRIKE_DELAY,
+        },
+        SpriteBundle {
+            sprite: Sprite {
+                color: STRIKE_MARKER_COLOR,
+                custom_size: Some(Vec2::splat(STRIKE_RADIUS * 2.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: position.extend(0.),
+                rotation: Quat::from_rotation_z(PI / 4.),
+                ..default()
+            },
+            ..default()
+        },
+        YSort(-1.),
+        TimedDespawn {
+            delay: STRIKE_DELAY + STRIKE_IMPACT_DURATION,
+        },
+        GameDespawn,
+    ));
+}
+
+// Anything caught under the bolt is hit, the player through the usual contact damage
+fn strike_lightning(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ingame_time: Res<IngameTime>,
+    mut strike_query: Query<(Entity, &LightningStrike, &Transform, &mut Sprite)>,
+    enemies_query: Query<(Entity, &Transform), With<Enemy>>,
+    mut ev_damage: EventWriter<DamageEvent>,
+) {
+    for (entity, strike, transform, mut sprite) in strike_query.iter_mut() {
+        if ingame_time.0 < strike.lands_at {
+            continue;
+        }
+
+        let position = transform.translation.xy();
+        for (enemy_entity, enemy_transform) in enemies_query.iter() {
+            if enemy_transform.translation.xy().distance(position) <= STRIKE_RADIUS {
+                ev_damage.send(DamageEvent {
+                    damage: STRIKE_DAMAGE,
+                    entity: enemy_entity,
+                    kind: DamageKind::Lightning,
+                });
+            }
+        }
+
+        sprite.color = STRIKE_MARKER_COLOR.with_a(1.);
+        commands.entity(entity).remove::<LightningStrike>().insert((
+            ContactEnemy,
+            Sensor,
+            sensor_groups(),
+            Collider::ball(STRIKE_RADIUS),
+            EnemyKnockback {
+                knockback: STRIKE_KNOCKBACK,
+            },
+        ));
+
+        let fade_tween = Tween::new(
+            EaseMethod::Linear,
+            Duration::from_secs_f32(1.),
+            SpriteColorLens {
+                start: Color::WHITE,
+                end: Color::BLACK.with_a(0.),
+            },
+        )
+        .with_completed_event(0);
+
+        commands.spawn((
+            SpriteBundle {
+                texture: asset_server.load("sprites/projectiles/zeus_thunderbolt.png"),
+                transform: Transform {
+                    translation: position.extend(0.),
+                    scale: Vec3::splat(2.),
+                    ..default()
+                },
+                sprite: Sprite {
+                    anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                ..default()
+            },
+            Animator::new(fade_tween),
+            GameDespawn,
+            TweenDespawn,
+            YSort(0.),
+        ));
+    }
+}
+
+// F1 to F4 hold the weather in one state for a full stretch
+#[cfg(debug_assertions)]
+fn force_weather(
+    keys: Res<ButtonInput<KeyCode>>,
+    ingame_time: Res<IngameTime>,
+    mut weather: ResMut<Weather>,
+    mut ev_show_banner: EventWriter<ShowBanner>,
+) {
+    let forced = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4]
+        .into_iter()
+        .zip(WEATHER_STATES)
+        .find(|(key, _)| keys.just_pressed(*key));
+
+    if let Some((_, state)) = forced {
+        weather.set(state, ingame_time.0);
+        ev_show_banner.send(ShowBanner(state.name().into()));
+    }
+}
+
+fn reset_weather(mut weather: ResMut<Weather>) {
+    *weather = Weather::default();
+}
+
+fn reset_overlays(mut overlay_query: Query<&mut Sprite, With<Overlay>>) {
+    for mut sprite in overlay_query.iter_mut() {
+        sprite.color.set_a(0.);
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use bevy_tweening::{lens::SpriteColorLens, Animator, EaseMethod, Tween};
+use rand::{thread_rng, Rng};
+use std::{f32::consts::PI, time::Duration};
+
+use super::{
+    biomes::value_noise, environment::WorldSeed, player::Player, weather::Weather, GameDespawn,
+    GameState, IngameTime, Movement, PauseState, TimedDespawn, YSort,
+};
+
+pub struct WindPlugin;
+
+impl Plugin for WindPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Wind::default())
+            .add_systems(
+                Update,
+                (update_wind, trim_sails, spawn_wind_particles)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(in_state(PauseState::Running)),
+            )
+            .add_systems(OnEnter(GameState::Game), (reset_wind, reset_sail_trim));
+    }
+}
+
+// Seconds for the wind to veer through one noise cell
+const WIND_PERIOD: f32 = 45.;
+const WIND_MIN_STRENGTH: f32 = 20.;
+const WIND_MAX_STRENGTH: f32 = 80.;
+
+// World units across one current cell
+const CURRENT_SCALE: f32 = 1500.;
+// Seconds for the currents to shift through one noise cell
+const CURRENT_PERIOD: f32 = 120.;
+const CURRENT_STRENGTH: f32 = 60.;
+// Keeps the noise fields from lining up
+const STRENGTH_SALT: u64 = 0x2545_F491_4F6C_DD1D;
+const CURRENT_SALT: u64 = 0x9FB2_1C65_1E98_DF25;
+
+// Trim goes from loose to full in this many seconds
+const TRIM_TIME: f32 = 0.75;
+// Fully trimmed running with the wind, and beating into it
+const TRIM_BONUS: f32 = 0.6;
+const TRIM_PENALTY: f32 = 0.3;
+
+// Per second at full wind strength
+const PARTICLE_RATE: f32 = 12.;
+const PARTICLE_LIFETIME: f32 = 2.5;
+const PARTICLE_SPREAD: Vec2 = Vec2::new(700., 400.);
+const PARTICLE_DRIFT: f32 = 3.;
+
+// The wind blows everywhere, currents vary across the map. Both are accelerations
+#[derive(Resource, Default)]
+pub struct Wind {
+    pub angle: f32,
+    pub strength: f32,
+    // How far the water surface has been blown, for scrolling the tiles
+    pub scroll: Vec2,
+    seed: u64,
+    time: f32,
+}
+
+impl Wind {
+    pub fn direction(&self) -> Vec2 {
+        Vec2::from_angle(self.angle)
+    }
+
+    pub fn current_at(&self, position: Vec2) -> Vec2 {
+        let sample = position / CURRENT_SCALE + Vec2::splat(self.time / CURRENT_PERIOD);
+        let angle = value_noise(self.seed ^ CURRENT_SALT, sample) * 4. * PI;
+        let strength = value_noise(self.seed ^ CURRENT_SALT ^ STRENGTH_SALT, sample);
+        // Only the stronger half of the field flows, leaving still water in between
+        Vec2::from_angle(angle) * CURRENT_STRENGTH * ((strength - 0.5) * 2.).max(0.)
+    }
+
+    pub fn flow_at(&self, position: Vec2) -> Vec2 {
+        self.direction() * self.strength + self.current_at(position)
+    }
+}
+
+// How strongly the wind and currents push this entity
+#[derive(Component)]
+pub struct Drift(pub f32);
+
+// Held with Shift, speeds the ship up running with the wind and slows it beating into it
+#[derive(Component)]
+pub struct SailTrim {
+    trim: f32,
+    pub multiplier: f32,
+}
+
+impl Default for SailTrim {
+    fn default() -> Self {
+        SailTrim {
+            trim: 0.,
+            multiplier: 1.,
+        }
+    }
+}
+
+#[derive(Component)]
+struct WindParticle;
+
+fn update_wind(
+    time: Res<Time>,
+    ingame_time: Res<IngameTime>,
+    world_seed: Res<WorldSeed>,
+    weather: Res<Weather>,
+    mut wind: ResMut<Wind>,
+) {
+    let sample = Vec2::new(ingame_time.0 / WIND_PERIOD, 0.);
+
+    wind.seed = world_seed.0;
+    wind.time = ingame_time.0;
+    wind.angle = value_noise(world_seed.0, sample) * 4. * PI;
+    wind.strength = WIND_MIN_STRENGTH
+        + (WIND_MAX_STRENGTH - WIND_MIN_STRENGTH)
+            * value_noise(world_seed.0 ^ STRENGTH_SALT, sample);
+    wind.strength *= weather.wind_multiplier();
+
+    let blown = wind.direction() * wind.strength / WIND_MAX_STRENGTH * time.delta_seconds();
+    wind.scroll += blown;
+}
+
+fn trim_sails(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    wind: Res<Wind>,
+    mut player_query: Query<(&Movement, &mut SailTrim), With<Player>>,
+) {
+    let Ok((movement, mut sail_trim)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let target = if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight) {
+        1.
+    } else {
+        0.
+    };
+    let step = time.delta_seconds() / TRIM_TIME;
+    sail_trim.trim += (target - sail_trim.trim).clamp(-step, step);
+
+    let alignment = movement.velocity.normalize_or_zero().dot(wind.direction());
+    let strength = wind.strength / WIND_MAX_STRENGTH;
+    sail_trim.multiplier = 1.
+        + sail_trim.trim
+            * strength
+            * (TRIM_BONUS * alignment.max(0.) - TRIM_PENALTY * (-alignment).max(0.));
+}
+
+fn spawn_wind_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    wind: Res<Wind>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let mut rng = thread_rng();
+    let chance = PARTICLE_RATE * wind.strength / WIND_MAX_STRENGTH * time.delta_seconds();
+    if !rng.gen_bool(chance.clamp(0., 1.).into()) {
+        return;
+    }
+
+    let position = player_transform.translation.xy()
+        + Vec2::new(
+            rng.gen_range(-PARTICLE_SPREAD.x..PARTICLE_SPREAD.x),
+            rng.gen_range(-PARTICLE_SPREAD.y..PARTICLE_SPREAD.y),
+        );
+    let flow = wind.flow_at(position);
+
+    let fade_tween = Tween::new(
+        EaseMethod::Linear,
+        Duration::from_secs_f32(PARTICLE_LIFETIME),
+        SpriteColorLens {
+            start: Color::WHITE.with_a(0.5),
+            end: Color::WHITE.with_a(0.),
+        },
+    );
+
+    commands.spawn((
+        WindParticle,
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::WHITE.with_a(0.5),
+                custom_size: Some(Vec2::new(10., 1.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: position.extend(0.),
+                rotation: Quat::from_rotation_z(flow.to_angle()),
+                ..default()
+            },
+            ..default()
+        },
+        Animator::new(fade_tween),
+        Drift(PARTICLE_DRIFT),
+        YSort(-1.),
+        TimedDespawn {
+            delay: PARTICLE_LIFETIME,
+        },
+        GameDespawn,
+    ));
+}
+
+fn reset_wind(mut wind: ResMut<Wind>) {
+    wind.scroll = Vec2::ZERO;
+}
+
+fn reset_sail_trim(mut player_query: Query<&mut SailTrim, With<Player>>) {
+    for mut sail_trim in player_query.iter_mut() {
+        *sail_trim = SailTrim::default();
+    }
+}